@@ -1,3 +1,55 @@
+// Commands the webview may invoke; each one needs its "allow-*" permission in
+// capabilities/default.json. Internal helpers (run_command_with_output) are not listed.
+const COMMANDS: &[&str] = &[
+  "run_pandoc",
+  "open_file",
+  "select_input_file",
+  "select_output_dir",
+  "file_exists",
+  "get_downloads_path",
+  "reveal_in_finder",
+  "get_app_version",
+  "detect_dependency",
+  "install_dependency",
+  "uninstall_dependency",
+  "reinstall_dependency",
+  "cancel_all_installs",
+  "list_system_fonts",
+  "list_font_catalog",
+  "list_project_fonts",
+  "preview_unicode_header",
+  "scan_font_coverage",
+  "list_output_themes",
+  "save_output_theme",
+  "delete_output_theme",
+  "run_doctor",
+  "doctor_report_markdown",
+  "get_diagram_cache_size",
+  "clear_diagram_cache",
+  "list_lua_filters",
+  "add_lua_filter",
+  "remove_lua_filter",
+  "check_citations",
+  "list_templates",
+  "import_template",
+  "export_default_template",
+  "delete_template",
+  "inspect_template_variables",
+  "list_community_templates",
+  "install_community_template",
+  "uninstall_community_template",
+  "open_project",
+  "rescan_project",
+  "save_project",
+  "watch_project",
+  "unwatch_project",
+  "detect_input_format",
+];
+
 fn main() {
-  tauri_build::build()
+  tauri_build::try_build(
+    tauri_build::Attributes::new()
+      .app_manifest(tauri_build::AppManifest::new().commands(COMMANDS)),
+  )
+  .expect("failed to run tauri-build")
 }
@@ -0,0 +1,580 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::scope;
+use crate::workspace::JobWorkspace;
+
+// CSL styles shipped with the app: id, name and style file. APA and IEEE are simplified
+// approximations, labelled "(condensed)" so they aren't mistaken for the official CSL styles.
+// Chicago author-date is pandoc's built-in default, so it needs no file
+const BUILTIN_STYLES: &[(&str, &str, Option<&str>)] = &[
+    ("chicago", "Chicago (author-date)", None),
+    ("apa", "APA 7th edition (condensed)", Some(include_str!("csl/apa.csl"))),
+    ("ieee", "IEEE (condensed)", Some(include_str!("csl/ieee.csl"))),
+];
+
+// pandoc-crossref references use the same @ syntax but aren't citations
+const CROSSREF_PREFIXES: &[&str] = &["fig:", "sec:", "tbl:", "eq:", "lst:"];
+
+// Input extensions scanned for @key citations; other formats carry citations differently
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "txt", "rmd", "qmd"];
+
+// Bibliography, style and locale for a job; paths may be relative to the input's folder
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationOptions {
+    #[serde(default)]
+    pub bibliographies: Vec<String>,
+    // Built-in style id (chicago, apa, ieee) or a path to a .csl file
+    pub style: Option<String>,
+    // BCP 47 language tag passed as the lang metadata field, which citeproc uses for its locale
+    pub locale: Option<String>,
+}
+
+// A bibliography file that parsed, with its entry keys in file order (duplicates included)
+pub struct Bibliography {
+    pub path: PathBuf,
+    pub keys: Vec<String>,
+}
+
+fn resolve_path(path: &str, input: Option<&str>) -> PathBuf {
+    let path = Path::new(path.trim());
+    match input.and_then(|p| Path::new(p).parent()) {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn line_of(chars: &[char], pos: usize) -> usize {
+    chars[..pos].iter().filter(|c| **c == '\n').count() + 1
+}
+
+// Entry keys of a BibTeX/BibLaTeX file; @comment, @string and @preamble have no key
+fn bibtex_keys(text: &str) -> Result<Vec<String>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '@' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        let type_start = i;
+        while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+            i += 1;
+        }
+        let entry_type: String = chars[type_start..i].iter().collect::<String>().to_lowercase();
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        // Text outside entries is a comment in BibTeX, so a stray @ (an e-mail address) isn't an entry
+        let close = match chars.get(i) {
+            Some('{') if !entry_type.is_empty() => '}',
+            Some('(') if !entry_type.is_empty() => ')',
+            _ => continue,
+        };
+
+        // Braces nest inside field values; the entry ends at its closing delimiter outside them
+        i += 1;
+        let body_start = i;
+        let mut depth = 0;
+        loop {
+            match chars.get(i) {
+                None => return Err(format!("line {}: @{} entry is never closed (unbalanced braces)", line_of(&chars, start), entry_type)),
+                Some('{') => depth += 1,
+                Some('}') if depth > 0 => depth -= 1,
+                Some(c) if *c == close && depth == 0 => break,
+                _ => {}
+            }
+            i += 1;
+        }
+        let body: String = chars[body_start..i].iter().collect();
+        i += 1;
+
+        if matches!(entry_type.as_str(), "comment" | "string" | "preamble") {
+            continue;
+        }
+        let key = body.split(',').next().unwrap_or_default().trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(format!("line {}: @{} entry has no valid citation key", line_of(&chars, start), entry_type));
+        }
+        keys.push(key.to_string());
+    }
+
+    Ok(keys)
+}
+
+// `id` of each item in a CSL JSON array
+fn csl_json_keys(text: &str) -> Result<Vec<String>, String> {
+    let value: serde_json::Value = serde_json::from_str(text)
+        .map_err(|e| format!("invalid JSON: {}", e))?;
+    let items = value.as_array()
+        .ok_or_else(|| "expected a JSON array of CSL items".to_string())?;
+
+    items.iter().enumerate().map(|(index, item)| {
+        match item.get("id") {
+            Some(serde_json::Value::String(id)) => Ok(id.clone()),
+            Some(serde_json::Value::Number(id)) => Ok(id.to_string()),
+            _ => Err(format!("item {} has no \"id\"", index + 1)),
+        }
+    }).collect()
+}
+
+// `id` of each item in a CSL YAML file: a `references:` list (as written by pandoc or Zotero) or a
+// bare list of items
+fn csl_yaml_keys(text: &str) -> Result<Vec<String>, String> {
+    let value: serde_yaml::Value = serde_yaml::from_str(text)
+        .map_err(|e| format!("invalid YAML: {}", e))?;
+    let items = match &value {
+        serde_yaml::Value::Sequence(items) => items,
+        serde_yaml::Value::Mapping(_) => value.get("references")
+            .and_then(serde_yaml::Value::as_sequence)
+            .ok_or_else(|| "expected a `references:` list of CSL items".to_string())?,
+        _ => return Err("expected a `references:` list of CSL items".to_string()),
+    };
+
+    items.iter().enumerate().map(|(index, item)| {
+        match item.get("id") {
+            Some(serde_yaml::Value::String(id)) => Ok(id.clone()),
+            Some(serde_yaml::Value::Number(id)) => Ok(id.to_string()),
+            _ => Err(format!("reference {} has no `id`", index + 1)),
+        }
+    }).collect()
+}
+
+// Parse a bibliography file and return its keys; the format comes from the extension, as in pandoc
+pub fn bibliography_keys(path: &Path) -> Result<Vec<String>, String> {
+    let extension = path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read bibliography {}: {}", path.display(), e))?;
+
+    let keys = match extension.as_str() {
+        "bib" | "bibtex" => bibtex_keys(&text),
+        "json" => csl_json_keys(&text),
+        "yaml" | "yml" => csl_yaml_keys(&text),
+        _ => return Err(format!("Unsupported bibliography format: {} (use .bib, .json or .yaml)", path.display())),
+    };
+    keys.map_err(|e| format!("Failed to parse bibliography {}: {}", path.display(), e))
+}
+
+pub fn load_bibliographies(options: &CitationOptions, input: Option<&str>) -> Result<Vec<Bibliography>, String> {
+    options.bibliographies.iter()
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| {
+            let path = resolve_path(p, input);
+            if !path.is_file() {
+                return Err(format!("Bibliography not found: {}", path.display()));
+            }
+            Ok(Bibliography { keys: bibliography_keys(&path)?, path })
+        })
+        .collect()
+}
+
+// Whether pandoc would read @key citations from this input
+pub fn is_markdown(input: &str) -> bool {
+    Path::new(input).extension()
+        .map(|ext| MARKDOWN_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn is_list_item(trimmed: &str) -> bool {
+    let bullet = ["- ", "* ", "+ "].iter().any(|m| trimmed.starts_with(m));
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    bullet || (digits > 0 && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") ")))
+}
+
+// Drop text between matching backtick runs and inside <!-- --> comments, which may span lines
+fn strip_inline(line: &str, in_comment: &mut bool, out: &mut String) {
+    let mut rest = line;
+    while !rest.is_empty() {
+        if *in_comment {
+            match rest.find("-->") {
+                Some(end) => {
+                    rest = &rest[end + 3..];
+                    *in_comment = false;
+                }
+                None => return,
+            }
+            continue;
+        }
+        let comment = rest.find("<!--");
+        let code = rest.find('`');
+        match (comment, code) {
+            (Some(start), code) if !matches!(code, Some(c) if c < start) => {
+                out.push_str(&rest[..start]);
+                out.push(' ');
+                rest = &rest[start + 4..];
+                *in_comment = true;
+            }
+            (_, Some(start)) => {
+                out.push_str(&rest[..start]);
+                out.push(' ');
+                let ticks = rest[start..].chars().take_while(|c| *c == '`').count();
+                let after = &rest[start + ticks..];
+                // An unmatched run is literal text
+                match after.find(&"`".repeat(ticks)) {
+                    Some(end) => rest = &after[end + ticks..],
+                    None => {
+                        out.push_str(after);
+                        return;
+                    }
+                }
+            }
+            (_, None) => {
+                out.push_str(rest);
+                return;
+            }
+        }
+    }
+}
+
+// Blank out what pandoc doesn't read citations from: fenced and indented code blocks, inline code,
+// HTML comments and raw LaTeX environments (\begin{...} ... \end{...} at the start of a line)
+fn strip_code(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut fence: Option<String> = None;
+    let mut raw_env: Option<String> = None;
+    let mut in_comment = false;
+    let mut in_list = false;
+    let mut previous_blank = true;
+    let mut in_indented_code = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let blank = trimmed.is_empty();
+        let indented = line.starts_with("    ") || line.starts_with('\t');
+        let marker: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+
+        if let Some(open) = &fence {
+            if marker.len() >= open.len() && marker.starts_with(&open[..1]) && trimmed[marker.len()..].trim().is_empty() {
+                fence = None;
+            }
+        } else if let Some(env) = &raw_env {
+            if trimmed.starts_with(&format!("\\end{{{}}}", env)) {
+                raw_env = None;
+            }
+        } else if in_comment {
+            strip_inline(line, &mut in_comment, &mut out);
+        } else if marker.len() >= 3 && marker.chars().all(|c| c == marker.chars().next().unwrap()) {
+            fence = Some(marker);
+        } else if let Some(env) = trimmed.strip_prefix("\\begin{").and_then(|rest| rest.split('}').next()).filter(|_| !indented) {
+            if !trimmed.contains(&format!("\\end{{{}}}", env)) {
+                raw_env = Some(env.to_string());
+            }
+        } else if indented && !in_list && (previous_blank || in_indented_code) && !blank {
+            // Indented code block; inside lists the same indent continues the item
+            in_indented_code = true;
+        } else {
+            if !blank {
+                in_indented_code = false;
+                if is_list_item(trimmed) {
+                    in_list = true;
+                } else if !indented {
+                    in_list = false;
+                }
+            }
+            strip_inline(line, &mut in_comment, &mut out);
+        }
+        previous_blank = blank;
+        out.push('\n');
+    }
+    out
+}
+
+// Citation keys used in Markdown (`[@key]`, `@key`, `[-@key]`, `@{key}`) in order of first use;
+// e-mail addresses and pandoc-crossref references are skipped
+pub fn cited_keys(markdown: &str) -> Vec<String> {
+    let chars: Vec<char> = strip_code(markdown).chars().collect();
+    let is_key_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut seen = HashSet::new();
+    let mut keys = Vec::new();
+
+    for i in 0..chars.len() {
+        if chars[i] != '@' {
+            continue;
+        }
+        if i > 0 && (is_key_char(chars[i - 1]) || matches!(chars[i - 1], '\\' | '/' | '@')) {
+            continue;
+        }
+
+        let key: String = if chars.get(i + 1) == Some(&'{') {
+            let end = chars[i + 2..].iter().position(|c| *c == '}').map(|p| i + 2 + p);
+            match end {
+                Some(end) => chars[i + 2..end].iter().collect(),
+                None => continue,
+            }
+        } else {
+            let mut end = i + 1;
+            while end < chars.len() {
+                let c = chars[end];
+                let internal = ":.#$%&-+?<>~/".contains(c) && chars.get(end + 1).is_some_and(|n| is_key_char(*n));
+                if is_key_char(c) || (internal && end > i + 1) {
+                    end += 1;
+                } else {
+                    break;
+                }
+            }
+            chars[i + 1..end].iter().collect()
+        };
+
+        let key = key.trim().to_string();
+        if key.is_empty() || CROSSREF_PREFIXES.iter().any(|p| key.starts_with(p)) {
+            continue;
+        }
+        if seen.insert(key.clone()) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+// Keys cited in the document that no bibliography defines
+pub fn undefined_keys(cited: &[String], bibliographies: &[Bibliography]) -> Vec<String> {
+    let defined: HashSet<&str> = bibliographies.iter()
+        .flat_map(|b| b.keys.iter().map(String::as_str))
+        .collect();
+    cited.iter().filter(|key| !defined.contains(key.as_str())).cloned().collect()
+}
+
+// A key defined more than once, with the file of each definition
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateKey {
+    pub key: String,
+    pub files: Vec<String>,
+}
+
+// Cited keys compared with the bibliographies; pandoc only prints warnings for these
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationReport {
+    pub cited: Vec<String>,
+    pub defined: usize,
+    pub undefined: Vec<String>,
+    pub unused: Vec<String>,
+    pub duplicates: Vec<DuplicateKey>,
+}
+
+pub fn citation_report(markdown: &str, bibliographies: &[Bibliography]) -> CitationReport {
+    let cited = cited_keys(markdown);
+    let undefined = undefined_keys(&cited, bibliographies);
+
+    let mut definitions: Vec<(&str, &Path)> = Vec::new();
+    for bibliography in bibliographies {
+        definitions.extend(bibliography.keys.iter().map(|key| (key.as_str(), bibliography.path.as_path())));
+    }
+
+    let mut duplicates: Vec<DuplicateKey> = Vec::new();
+    let mut unused: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    // `nocite: @*` puts every entry in the bibliography
+    let cites_all = markdown.contains("@*");
+    for (key, _) in &definitions {
+        if !seen.insert(*key) {
+            continue;
+        }
+        let files: Vec<String> = definitions.iter()
+            .filter(|(other, _)| other == key)
+            .map(|(_, path)| path.to_string_lossy().to_string())
+            .collect();
+        if files.len() > 1 {
+            duplicates.push(DuplicateKey { key: key.to_string(), files });
+        }
+        if !cites_all && !cited.iter().any(|c| c == key) {
+            unused.push(key.to_string());
+        }
+    }
+
+    CitationReport { defined: seen.len(), cited, undefined, unused, duplicates }
+}
+
+// A BCP 47 tag like en, en-US or zh-Hant-TW
+fn valid_locale(locale: &str) -> bool {
+    let mut parts = locale.split('-');
+    let language = parts.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|p| (1..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+// --bibliography, --csl and --metadata=lang arguments, one per element; built-in styles are written into the workspace
+pub fn pandoc_args(
+    options: &CitationOptions,
+    bibliographies: &[Bibliography],
+    input: Option<&str>,
+    workspace: &JobWorkspace,
+) -> Result<Vec<String>, String> {
+    let mut args: Vec<String> = bibliographies.iter()
+        .map(|b| format!("--bibliography={}", b.path.to_string_lossy()))
+        .collect();
+
+    if let Some(style) = options.style.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        match BUILTIN_STYLES.iter().find(|(id, ..)| *id == style) {
+            Some((_, _, None)) => {},
+            Some((id, _, Some(source))) => {
+                let path = workspace.write(&format!("{}.csl", id), source)?;
+                args.push(format!("--csl={}", path.to_string_lossy()));
+            },
+            None => {
+                let path = resolve_path(style, input);
+                if !path.is_file() {
+                    return Err(format!("CSL style not found: {}", path.display()));
+                }
+                let content = fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read CSL style {}: {}", path.display(), e))?;
+                if !content.contains("<style") {
+                    return Err(format!("Not a CSL style file: {}", path.display()));
+                }
+                args.push(format!("--csl={}", path.to_string_lossy()));
+            },
+        }
+    }
+
+    if let Some(locale) = options.locale.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        if !valid_locale(locale) {
+            return Err(format!("Invalid citation locale '{}': use a language tag like en-US or de-DE", locale));
+        }
+        args.push(format!("--metadata=lang={}", locale));
+    }
+
+    Ok(args)
+}
+
+// Text of the Markdown inputs, joined the way pandoc concatenates multiple input files
+pub fn read_markdown<P: AsRef<Path>>(inputs: &[P]) -> Result<String, String> {
+    let mut texts = Vec::new();
+    for input in inputs.iter().map(AsRef::as_ref).filter(|p| is_markdown(&p.to_string_lossy())) {
+        texts.push(fs::read_to_string(input)
+            .map_err(|e| format!("Failed to read {}: {}", input.display(), e))?);
+    }
+    Ok(texts.join("\n\n"))
+}
+
+// Compare the input's citations with the configured bibliographies before converting; a book
+// project passes all its chapters, with the first one as the input
+#[tauri::command]
+pub async fn check_citations(input_path: String, citations: CitationOptions, chapters: Option<Vec<String>>) -> Result<CitationReport, String> {
+    let mut inputs = vec![scope::check_scoped(&input_path)?];
+    for chapter in chapters.unwrap_or_default().iter().filter(|c| **c != input_path) {
+        inputs.push(scope::check_scoped(chapter)?);
+    }
+    if !inputs.iter().any(|p| is_markdown(&p.to_string_lossy())) {
+        return Err("Citation checks need Markdown input".to_string());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let bibliographies = load_bibliographies(&citations, Some(&input_path))?;
+        let text = read_markdown(&inputs)?;
+        Ok(citation_report(&text, &bibliographies))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bibtex_keys_skip_comments_and_stray_at_signs() {
+        let bib = "Maintained by jane@example.org\n\n@comment{ignored}\n@string{acm = \"ACM\"}\n\
+                   @article{smith2020,\n  title = {A {Nested} Title},\n}\n@book(doe-2019, title = {B})\n";
+        assert_eq!(bibtex_keys(bib).unwrap(), vec!["smith2020", "doe-2019"]);
+    }
+
+    #[test]
+    fn bibtex_errors_report_the_entry_line() {
+        let unclosed = "@book{ok, title = {A}}\n\n@article{smith2020,\n  title = {Unbalanced\n";
+        assert_eq!(bibtex_keys(unclosed).unwrap_err(), "line 3: @article entry is never closed (unbalanced braces)");
+
+        let no_key = "\n@misc{ , title = {A}}\n";
+        assert_eq!(bibtex_keys(no_key).unwrap_err(), "line 2: @misc entry has no valid citation key");
+    }
+
+    #[test]
+    fn csl_json_keys_and_errors() {
+        assert_eq!(csl_json_keys(r#"[{"id": "smith2020"}, {"id": 42}]"#).unwrap(), vec!["smith2020", "42"]);
+        assert_eq!(csl_json_keys(r#"[{"id": "a"}, {"title": "B"}]"#).unwrap_err(), "item 2 has no \"id\"");
+
+        let error = csl_json_keys("[\n  {\"id\": \"a\"},\n  {\"id\": }\n]").unwrap_err();
+        assert!(error.starts_with("invalid JSON:") && error.contains("line 3"), "{}", error);
+    }
+
+    #[test]
+    fn csl_yaml_keys_and_errors() {
+        let yaml = "\
+---
+references:
+- id: smith2020
+  title: \"Tabs\tinside values are fine\"
+  note: |
+    id: not-a-reference
+  container:
+    id: nested-map
+- type: book
+  id: 'doe-2019'
+...
+";
+        assert_eq!(csl_yaml_keys(yaml).unwrap(), vec!["smith2020", "doe-2019"]);
+        assert_eq!(csl_yaml_keys("- id: 42\n- id: bare\n").unwrap(), vec!["42", "bare"]);
+        assert!(csl_yaml_keys("references: []\n").unwrap().is_empty());
+
+        assert_eq!(csl_yaml_keys("references:\n- id: a\n- title: B\n").unwrap_err(), "reference 2 has no `id`");
+        let tabbed = csl_yaml_keys("references:\n- id: smith2020\n\ttitle: A\n").unwrap_err();
+        assert!(tabbed.starts_with("invalid YAML:") && tabbed.contains("line 3"), "{}", tabbed);
+        let unclosed = csl_yaml_keys("references:\n- id: a\n  title: [unclosed\n").unwrap_err();
+        assert!(unclosed.contains("line"), "{}", unclosed);
+    }
+
+    #[test]
+    fn cited_keys_in_order_of_first_use() {
+        let markdown = "As [@smith2020, p. 4] and @doe-2019 show [-@smith2020; @{weird key}].\n";
+        assert_eq!(cited_keys(markdown), vec!["smith2020", "doe-2019", "weird key"]);
+    }
+
+    #[test]
+    fn cited_keys_skip_emails_crossrefs_and_escapes() {
+        let markdown = "Mail jane@example.org or \\@notakey, see @fig:plot and @sec:intro, then @real.\n";
+        assert_eq!(cited_keys(markdown), vec!["real"]);
+    }
+
+    #[test]
+    fn cited_keys_skip_code_comments_and_raw_blocks() {
+        let markdown = "\
+Inline `@inline` and ``@double `tick` `` code.
+
+```python
+@decorator
+```
+
+~~~~
+@tilde
+~~~~
+
+    @indented
+
+<!-- @comment
+spanning @lines -->
+
+\\begin{verbatim}
+@raw
+\\end{verbatim}
+
+Kept @kept.
+";
+        assert_eq!(cited_keys(markdown), vec!["kept"]);
+    }
+
+    #[test]
+    fn indented_list_continuations_are_not_code() {
+        let markdown = "- First item\n\n    continued with @listed\n\nParagraph\n    @lazy continuation\n";
+        assert_eq!(cited_keys(markdown), vec!["listed", "lazy"]);
+    }
+}
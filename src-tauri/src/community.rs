@@ -0,0 +1,536 @@
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::deps::{emit_phase, find_in_path};
+use crate::templates::{TemplateVariable, VariableKind};
+use crate::workspace::{JobWorkspace, shell_quote};
+use crate::{get_extended_path, run_command_with_output, slug};
+
+// What the app installed into pandoc's templates folder, so updates and uninstalls only touch those files
+const INSTALLED_FILE: &str = "community-templates.json";
+
+// Template files pandoc looks up by name (`--template eisvogel` finds eisvogel.latex for LaTeX output)
+const TEMPLATE_EXTENSIONS: &[&str] = &["latex", "beamer", "html", "html5", "revealjs", "context", "epub3", "ms"];
+
+// Folders in release archives that hold sample documents rather than the template itself
+const SKIPPED_DIRS: &[&str] = &["example", "examples", "test", "tests", "docs"];
+
+// A template the app knows how to fetch, with the variables it documents
+struct CatalogTemplate {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    version: &'static str,
+    url: &'static str,
+    // Lowercase hex SHA-256 of the archive at `url`; downloads that don't match are not unpacked,
+    // and an entry without one can't be installed
+    sha256: &'static str,
+    homepage: &'static str,
+    // Name, kind, default and description of each documented variable
+    variables: &'static [(&'static str, VariableKind, Option<&'static str>, &'static str)],
+}
+
+const CATALOG: &[CatalogTemplate] = &[
+    CatalogTemplate {
+        id: "eisvogel",
+        name: "Eisvogel",
+        description: "Clean LaTeX template for lecture notes, reports and books, with a colored title page",
+        version: "3.2.0",
+        url: "https://github.com/Wandmalfarbe/pandoc-latex-template/releases/download/v3.2.0/Eisvogel-3.2.0.tar.gz",
+        // Not pinned yet: fill in with `sha256sum Eisvogel-3.2.0.tar.gz` of the release download
+        sha256: "",
+        homepage: "https://github.com/Wandmalfarbe/pandoc-latex-template",
+        variables: &[
+            ("titlepage-color", VariableKind::Color, None, "Title page background color"),
+            ("titlepage-text-color", VariableKind::Color, Some("5F5F5F"), "Title page text color"),
+            ("titlepage-rule-color", VariableKind::Color, Some("435488"), "Color of the rule above the title"),
+            ("titlepage-rule-height", VariableKind::Number, Some("4"), "Height of the title rule in points (0 hides it)"),
+            ("titlepage-logo", VariableKind::Text, None, "Image shown on the title page"),
+            ("logo-width", VariableKind::Text, Some("35mm"), "Width of the title page logo"),
+            ("titlepage-background", VariableKind::Text, None, "Full-page background image (PDF) for the title page"),
+            ("page-background", VariableKind::Text, None, "Background image (PDF) for every other page"),
+            ("page-background-opacity", VariableKind::Number, Some("0.2"), "Opacity of the page background"),
+            ("caption-justification", VariableKind::Text, Some("raggedright"), "Caption alignment: raggedright, centering or justified"),
+            ("code-block-font-size", VariableKind::Text, Some("\\small"), "LaTeX font size command for code blocks"),
+            ("watermark", VariableKind::Text, None, "Text printed diagonally across every page"),
+            ("disable-header-and-footer", VariableKind::Bool, None, "Leave out the template's header and footer"),
+            ("footnotes-pretty", VariableKind::Bool, None, "Indented footnotes with hanging numbers"),
+            ("table-use-row-colors", VariableKind::Bool, None, "Alternate row colors in tables"),
+            ("listings-disable-line-numbers", VariableKind::Bool, None, "No line numbers in listings code blocks"),
+            ("listings-no-page-break", VariableKind::Bool, None, "Keep listings code blocks on one page"),
+            ("book", VariableKind::Bool, None, "Book layout with chapters and two-sided pages"),
+        ],
+    },
+];
+
+// One community template installed by the app; `files` are names inside pandoc's templates folder
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledTemplate {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    // URL or archive it was installed from
+    pub source: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CommunityListing {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub homepage: Option<String>,
+    // Version the catalog installs; None for templates installed from elsewhere
+    pub latest_version: Option<String>,
+    pub installed: Option<InstalledTemplate>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CommunityTemplates {
+    pub dir: String,
+    pub templates: Vec<CommunityListing>,
+}
+
+// Where to get a template: a catalog entry, an https URL, or an archive picked in a file dialog
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "kebab-case", tag = "kind", content = "value")]
+pub enum TemplateSource {
+    Catalog(String),
+    Url(String),
+    Archive,
+}
+
+fn installed_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(INSTALLED_FILE))
+        .map_err(|e| format!("Could not find app data directory: {}", e))
+}
+
+pub fn load_installed(app: &AppHandle) -> Result<Vec<InstalledTemplate>, String> {
+    let path = installed_path(app)?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_installed(app: &AppHandle, installed: &[InstalledTemplate]) -> Result<(), String> {
+    let path = installed_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(installed)
+        .map_err(|e| format!("Failed to serialize installed templates: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Pandoc's user data directory as pandoc itself reports it, falling back to its documented default
+fn pandoc_data_dir() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
+
+    let reported = find_in_path("pandoc")
+        .and_then(|pandoc| Command::new(pandoc).arg("--version").env("PATH", get_extended_path()).output().ok())
+        .and_then(|output| {
+            // "User data directory: ~/.local/share/pandoc"; pandoc 2.x says "Default user data
+            // directory: ~/.local/share/pandoc or ~/.pandoc"
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .find_map(|line| line.split_once("data directory:").map(|(_, dir)| dir.trim().to_string()))
+        })
+        .map(|dir| dir.split(" or ").next().unwrap_or_default().to_string())
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| match dir.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(dir),
+        });
+    if let Some(dir) = reported {
+        return Ok(dir);
+    }
+
+    if cfg!(target_os = "windows") {
+        return dirs::data_dir()
+            .map(|dir| dir.join("pandoc"))
+            .ok_or_else(|| "Could not find the AppData folder".to_string());
+    }
+    let data_home = env::var("XDG_DATA_HOME").ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local").join("share"));
+    Ok(data_home.join("pandoc"))
+}
+
+pub fn templates_dir() -> Result<PathBuf, String> {
+    pandoc_data_dir().map(|dir| dir.join("templates"))
+}
+
+// Documented variables of a catalog template
+pub fn variables(id: &str) -> Vec<TemplateVariable> {
+    CATALOG.iter()
+        .find(|t| t.id == id)
+        .map(|t| t.variables.iter().map(|(name, kind, default, description)| TemplateVariable {
+            name: name.to_string(),
+            kind: *kind,
+            description: description.to_string(),
+            default: default.map(str::to_string),
+        }).collect())
+        .unwrap_or_default()
+}
+
+// First dotted number in a file name: Eisvogel-3.2.0.tar.gz gives 3.2.0
+fn version_from_name(name: &str) -> Option<String> {
+    let start = name.find(|c: char| c.is_ascii_digit())?;
+    let version: String = name[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let version = version.trim_end_matches('.');
+    version.contains('.').then(|| version.to_string())
+}
+
+fn is_template_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| TEMPLATE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Template files anywhere in an extracted archive, shallowest first, skipping example folders
+fn find_template_files(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else { continue };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if path.is_dir() {
+                if !SKIPPED_DIRS.contains(&name.as_str()) {
+                    pending.push(path);
+                }
+            } else if is_template_file(&path) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort_by_key(|path| (path.components().count(), path.clone()));
+    found
+}
+
+#[derive(Clone, Copy)]
+enum ArchiveType {
+    Zip,
+    Tar,
+    // A bare template file, used as-is
+    Template,
+}
+
+fn archive_type(name: &str) -> Result<ArchiveType, String> {
+    let lower = name.to_lowercase();
+    if lower.ends_with(".zip") {
+        Ok(ArchiveType::Zip)
+    } else if [".tar.gz", ".tgz", ".tar.xz", ".tar.bz2", ".tar"].iter().any(|ext| lower.ends_with(ext)) {
+        Ok(ArchiveType::Tar)
+    } else if is_template_file(Path::new(&lower)) {
+        Ok(ArchiveType::Template)
+    } else {
+        Err(format!("Unsupported template archive: {} (expected .zip, .tar.gz or a template file)", name))
+    }
+}
+
+// Shell command that unpacks an archive; GNU tar can't read zip files, so Linux uses unzip
+fn extract_command(archive: &Path, kind: &ArchiveType, dest: &Path) -> Option<String> {
+    let archive = shell_quote(&archive.to_string_lossy());
+    let dest = shell_quote(&dest.to_string_lossy());
+    match kind {
+        ArchiveType::Zip if cfg!(target_os = "linux") => Some(format!("unzip -o -q {} -d {} 2>&1", archive, dest)),
+        ArchiveType::Zip | ArchiveType::Tar => Some(format!("tar -xf {} -C {} 2>&1", archive, dest)),
+        ArchiveType::Template => None,
+    }
+}
+
+// Command that lists an archive's entries, one per line, with the same tool that extracts it
+fn list_command(archive: &Path, kind: &ArchiveType) -> Option<Command> {
+    let (program, args): (&str, &[&str]) = match kind {
+        ArchiveType::Zip if cfg!(target_os = "linux") => ("unzip", &["-Z1"]),
+        ArchiveType::Zip | ArchiveType::Tar => ("tar", &["-tf"]),
+        ArchiveType::Template => return None,
+    };
+    let mut command = Command::new(program);
+    command.args(args).arg(archive).env("PATH", get_extended_path());
+    Some(command)
+}
+
+// An archive entry that would be written outside the folder it is extracted into
+fn is_unsafe_entry(entry: &str) -> bool {
+    let bytes = entry.as_bytes();
+    let drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    entry.starts_with('/') || entry.starts_with('\\') || drive
+        || entry.split(['/', '\\']).any(|part| part == "..")
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+// Checks before anything is unpacked: the pinned checksum for catalog downloads, and entry paths
+// that stay inside the staging folder for every archive
+fn check_archive(archive: &Path, kind: &ArchiveType, sha256: Option<&str>) -> Result<(), String> {
+    let name = archive.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if let Some(expected) = sha256 {
+        if expected.is_empty() {
+            return Err(format!("{} has no pinned checksum in the catalog, so it can't be verified", name));
+        }
+        let actual = sha256_file(archive)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("Checksum mismatch for {}: expected {}, got {}", name, expected, actual));
+        }
+    }
+
+    let Some(mut command) = list_command(archive, kind) else { return Ok(()) };
+    let output = command.output()
+        .map_err(|e| format!("Failed to list {}: {}", name, e))?;
+    if !output.status.success() {
+        return Err(format!("Failed to list {}: {}", name, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let listing = String::from_utf8_lossy(&output.stdout);
+    match listing.lines().map(str::trim_end).find(|entry| is_unsafe_entry(entry)) {
+        Some(entry) => Err(format!("Refusing to unpack {}: entry '{}' points outside the archive folder", name, entry)),
+        None => Ok(()),
+    }
+}
+
+// Last path segment of a URL, kept only if it is a plain file name
+fn url_file_name(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let name = path.rsplit('/').next()?;
+    let plain = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+    plain.then(|| name.to_string())
+}
+
+#[tauri::command]
+pub fn list_community_templates(app: AppHandle) -> Result<CommunityTemplates, String> {
+    let installed = load_installed(&app)?;
+    let mut templates: Vec<CommunityListing> = CATALOG.iter().map(|t| CommunityListing {
+        id: t.id.to_string(),
+        name: t.name.to_string(),
+        description: t.description.to_string(),
+        homepage: Some(t.homepage.to_string()),
+        latest_version: Some(t.version.to_string()),
+        installed: installed.iter().find(|i| i.id == t.id).cloned(),
+    }).collect();
+    templates.extend(installed.iter()
+        .filter(|i| !CATALOG.iter().any(|t| t.id == i.id))
+        .map(|i| CommunityListing {
+            id: i.id.clone(),
+            name: i.name.clone(),
+            description: format!("Installed from {}", i.source),
+            homepage: None,
+            latest_version: None,
+            installed: Some(i.clone()),
+        }));
+
+    Ok(CommunityTemplates {
+        dir: templates_dir()?.to_string_lossy().to_string(),
+        templates,
+    })
+}
+
+// Download or unpack a template into pandoc's templates folder; progress is streamed on the
+// command-output channel like dependency installs. Installing again replaces the previous version
+#[tauri::command]
+pub async fn install_community_template(app: AppHandle, source: TemplateSource) -> Result<Option<InstalledTemplate>, String> {
+    let catalog = match &source {
+        TemplateSource::Catalog(id) => Some(CATALOG.iter()
+            .find(|t| t.id == id.as_str())
+            .ok_or_else(|| format!("Unknown community template: {}", id))?),
+        _ => None,
+    };
+
+    // An https download or a local archive, plus the file name that tells what it is
+    let (url, local, file_name) = match &source {
+        TemplateSource::Catalog(_) => {
+            let url = catalog.map(|t| t.url.to_string()).unwrap_or_default();
+            let name = url_file_name(&url).unwrap_or_default();
+            (Some(url), None, name)
+        },
+        TemplateSource::Url(url) => {
+            let url = url.trim();
+            if !url.starts_with("https://") || url.chars().any(|c| c.is_whitespace() || c == '"' || c == '\'') {
+                return Err(format!("Template URLs must be plain https:// links: {}", url));
+            }
+            let name = url_file_name(url).unwrap_or_else(|| "template.tar.gz".to_string());
+            (Some(url.to_string()), None, name)
+        },
+        TemplateSource::Archive => {
+            let picked = app.dialog()
+                .file()
+                .add_filter("Template archives", &["zip", "gz", "tgz", "xz", "bz2", "tar", "latex", "html"])
+                .blocking_pick_file();
+            let Some(picked) = picked else { return Ok(None) };
+            let path = picked.into_path()
+                .map_err(|e| format!("Invalid file selection: {}", e))?;
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            (None, Some(path), name)
+        },
+    };
+    let kind = archive_type(&file_name)?;
+
+    // Downloads and extracted files stay in a private staging folder that is removed afterwards
+    let staging = JobWorkspace::create()?;
+    let unpacked = staging.path().join("unpacked");
+    fs::create_dir_all(&unpacked)
+        .map_err(|e| format!("Failed to create {}: {}", unpacked.display(), e))?;
+    let archive = match &local {
+        Some(path) => path.clone(),
+        None if matches!(kind, ArchiveType::Template) => unpacked.join(&file_name),
+        None => staging.path().join(&file_name),
+    };
+
+    if let Some(url) = &url {
+        let download = format!("curl -fL --proto =https -o {} {} 2>&1", shell_quote(&archive.to_string_lossy()), shell_quote(url));
+        run_command_with_output(app.clone(), download, format!("Fetching {}", file_name)).await?;
+    }
+
+    // Nothing is extracted until the download matches its pin and no entry escapes `unpacked`
+    let checked = archive.clone();
+    let sha256 = catalog.map(|t| t.sha256);
+    tokio::task::spawn_blocking(move || check_archive(&checked, &kind, sha256))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))??;
+    emit_phase(&app, "verify", "success", format!("Checked {}", file_name));
+
+    match extract_command(&archive, &kind, &unpacked) {
+        Some(extract) => {
+            run_command_with_output(app.clone(), extract, format!("Unpacking {}", file_name)).await?;
+        },
+        None if local.is_some() => {
+            fs::copy(&archive, unpacked.join(&file_name))
+                .map_err(|e| format!("Failed to copy {}: {}", archive.display(), e))?;
+        },
+        // A downloaded template file was saved straight into `unpacked`
+        None => {},
+    }
+
+    // Catalog entries install only their own files (eisvogel.latex, eisvogel.beamer)
+    let mut files = find_template_files(&unpacked);
+    if let Some(catalog) = catalog {
+        files.retain(|path| path.file_stem().is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(catalog.id)));
+    }
+    let mut seen = Vec::new();
+    files.retain(|path| {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let first = !seen.contains(&name);
+        seen.push(name);
+        first
+    });
+    let first = files.first()
+        .ok_or_else(|| format!("No pandoc templates ({}) found in {}", TEMPLATE_EXTENSIONS.join(", "), file_name))?;
+
+    let id = match catalog {
+        Some(catalog) => catalog.id.to_string(),
+        None => slug(&first.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(), "template"),
+    };
+    let known = CATALOG.iter().find(|t| t.id == id);
+    let version = match catalog {
+        Some(catalog) => catalog.version.to_string(),
+        None => version_from_name(&file_name).unwrap_or_else(|| "unknown".to_string()),
+    };
+
+    let dir = templates_dir()?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let mut installed = load_installed(&app)?;
+    let previous = installed.iter().position(|t| t.id == id).map(|i| installed.remove(i));
+
+    let mut names = Vec::new();
+    for path in &files {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let target = dir.join(&name);
+        if target.exists() && !previous.as_ref().is_some_and(|p| p.files.contains(&name)) {
+            warn!("Replacing {} that was not installed by the app", target.display());
+        }
+        fs::copy(path, &target)
+            .map_err(|e| format!("Failed to install {}: {}", target.display(), e))?;
+        emit_phase(&app, "install", "success", format!("Installed {}", target.display()));
+        names.push(name);
+    }
+
+    // Files the previous version had that this one doesn't
+    for stale in previous.iter().flat_map(|p| &p.files).filter(|name| !names.contains(name)) {
+        let _ = fs::remove_file(dir.join(stale));
+    }
+
+    let template = InstalledTemplate {
+        name: known.map(|t| t.name.to_string()).unwrap_or_else(|| id.clone()),
+        id,
+        version,
+        source: url.unwrap_or_else(|| archive.to_string_lossy().to_string()),
+        files: names,
+    };
+    installed.push(template.clone());
+    save_installed(&app, &installed)?;
+    info!("Installed community template {} {} into {}", template.name, template.version, dir.display());
+    Ok(Some(template))
+}
+
+#[tauri::command]
+pub fn uninstall_community_template(app: AppHandle, id: String) -> Result<(), String> {
+    let mut installed = load_installed(&app)?;
+    let index = installed.iter()
+        .position(|t| t.id == id)
+        .ok_or_else(|| format!("Community template '{}' is not installed", id))?;
+    let template = installed.remove(index);
+
+    let dir = templates_dir()?;
+    for name in &template.files {
+        let path = dir.join(name);
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+        }
+    }
+    save_installed(&app, &installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_entries_pin_a_sha256() {
+        for template in CATALOG {
+            let pinned = template.sha256.len() == 64 && template.sha256.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase());
+            assert!(pinned, "{} has no pinned lowercase hex SHA-256", template.id);
+        }
+    }
+
+    #[test]
+    fn archive_entries_stay_in_their_folder() {
+        for entry in ["Eisvogel-3.2.0/eisvogel.latex", "templates/", "a..b/c.latex"] {
+            assert!(!is_unsafe_entry(entry), "{}", entry);
+        }
+        for entry in ["../evil.latex", "a/../../evil", "/etc/passwd", "\\\\server\\share", "C:\\evil", "a\\..\\..\\evil"] {
+            assert!(is_unsafe_entry(entry), "{}", entry);
+        }
+    }
+}
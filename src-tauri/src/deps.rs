@@ -0,0 +1,565 @@
+use std::process::Command;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use log::info;
+
+use crate::{cancel_requests, get_extended_path, run_command_with_output};
+
+// How much privilege a recipe needs before it can run
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Privilege {
+    // Runs entirely as the current user
+    None,
+    // Always prompts for an admin password (pkexec, sudo or osascript askpass)
+    Required,
+    // Tries as the current user first and only prompts if that fails
+    Fallback,
+}
+
+// A resolved install/uninstall recipe for one dependency
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Recipe {
+    pub manager: &'static str,
+    pub packages: Vec<&'static str>,
+    pub command: String,
+    pub privilege: Privilege,
+    // Which prompt is used when escalating: "osascript", "pkexec" or "sudo"
+    pub elevation: Option<&'static str>,
+    // Native package manager command that reports changes without applying them
+    pub dry_run_command: Option<String>,
+}
+
+// One step of a dry-run plan, with whatever the package manager reported
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedStep {
+    pub operation: String,
+    #[serde(flatten)]
+    pub recipe: Recipe,
+    pub dry_run_output: Option<String>,
+    pub changes: Vec<String>,
+    // False when the package manager has no dry-run for this step (brew/cargo uninstall), so
+    // `changes` is empty because nothing was checked, not because nothing would change
+    pub native_dry_run: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunPlan {
+    pub name: String,
+    pub steps: Vec<PlannedStep>,
+    // Some step could only list its commands rather than report what they would change
+    pub commands_only: bool,
+}
+
+// Result of an install/uninstall/reinstall command: either it ran, or it was only planned
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum DependencyOutcome {
+    Completed(String),
+    Planned(DryRunPlan),
+}
+
+// Wrap a command so sudo prompts through a native macOS password dialog
+// Homebrew can't run as root, so we provide an askpass helper instead of running under sudo
+fn with_askpass(purpose: &str, command: &str) -> String {
+    format!(
+        r#"ASKPASS_SCRIPT=$(mktemp) && cat > "$ASKPASS_SCRIPT" << 'ASKPASSEOF'
+#!/bin/bash
+osascript -e 'display dialog "Pandoc GUI needs your password to {}:" default answer "" with hidden answer buttons {{"Cancel","OK"}} default button "OK"' -e 'text returned of result' 2>/dev/null
+ASKPASSEOF
+chmod +x "$ASKPASS_SCRIPT" && SUDO_ASKPASS="$ASKPASS_SCRIPT" {}; EXIT_CODE=$?; rm -f "$ASKPASS_SCRIPT"; exit $EXIT_CODE"#,
+        purpose, command
+    )
+}
+
+fn recipe(manager: &'static str, packages: &[&'static str], command: String) -> Recipe {
+    Recipe {
+        manager,
+        packages: packages.to_vec(),
+        command,
+        privilege: Privilege::None,
+        elevation: None,
+        dry_run_command: None,
+    }
+}
+
+// apt install/remove through the GUI password prompt on Linux
+fn apt_recipe(action: &str, packages: &'static [&'static str]) -> Recipe {
+    let dry_run = format!("apt-get -s {} {} 2>&1", action, packages.join(" "));
+    if cfg!(target_os = "linux") {
+        let command = format!("pkexec apt {} -y {} 2>&1", action, packages.join(" "));
+        recipe("apt", packages, command).elevated(Privilege::Required, "pkexec").dry_run(dry_run)
+    } else {
+        let command = format!("sudo apt {} {} 2>&1", action, packages.join(" "));
+        recipe("apt", packages, command).elevated(Privilege::Required, "sudo").dry_run(dry_run)
+    }
+}
+
+impl Recipe {
+    fn elevated(mut self, privilege: Privilege, elevation: &'static str) -> Self {
+        self.privilege = privilege;
+        self.elevation = Some(elevation);
+        self
+    }
+
+    fn dry_run(mut self, command: String) -> Self {
+        self.dry_run_command = Some(command);
+        self
+    }
+}
+
+// Get the uninstall recipe for a dependency
+// Uses osascript on macOS for commands requiring admin privileges (shows native password dialog)
+pub fn uninstall_recipe(name: &str) -> Option<Recipe> {
+    match name {
+        "tectonic" => Some(recipe("brew", &["tectonic"], "brew uninstall tectonic 2>&1 || cargo uninstall tectonic 2>&1".to_string())),
+        "texlive" => {
+            let packages = &["basictex", "mactex"];
+            if cfg!(target_os = "macos") {
+                let command = with_askpass("uninstall BasicTeX", "brew uninstall --cask basictex 2>&1 || brew uninstall --cask mactex 2>&1");
+                Some(recipe("brew", packages, command).elevated(Privilege::Required, "osascript"))
+            } else {
+                Some(recipe("brew", packages, "brew uninstall --cask basictex 2>&1 || brew uninstall --cask mactex 2>&1".to_string()))
+            }
+        },
+        "mermaid-filter" => {
+            let packages = &["mermaid-filter"];
+            let dry_run = "npm uninstall -g --dry-run mermaid-filter 2>&1".to_string();
+            if cfg!(target_os = "macos") {
+                // Try without sudo first (works if npm prefix is user-writable), fall back to sudo with askpass
+                let command = format!("npm uninstall -g mermaid-filter 2>&1 || ({})", with_askpass("uninstall mermaid-filter", "sudo -A npm uninstall -g mermaid-filter 2>&1"));
+                Some(recipe("npm", packages, command).elevated(Privilege::Fallback, "osascript").dry_run(dry_run))
+            } else if cfg!(target_os = "linux") {
+                Some(recipe("npm", packages, "npm uninstall -g mermaid-filter 2>&1 || pkexec npm uninstall -g mermaid-filter 2>&1".to_string())
+                    .elevated(Privilege::Fallback, "pkexec")
+                    .dry_run(dry_run))
+            } else {
+                Some(recipe("npm", packages, "npm uninstall -g mermaid-filter 2>&1".to_string()).dry_run(dry_run))
+            }
+        },
+        "pandoc-crossref" => Some(recipe("brew", &["pandoc-crossref"], "brew uninstall pandoc-crossref 2>&1".to_string())),
+        "pandoc" => Some(recipe("brew", &["pandoc"], "brew uninstall pandoc 2>&1".to_string())),
+        // Diagram renderers come from apt on Linux and Homebrew elsewhere
+        "plantuml" if cfg!(target_os = "linux") => Some(apt_recipe("remove", &["plantuml"])),
+        "graphviz" if cfg!(target_os = "linux") => Some(apt_recipe("remove", &["graphviz"])),
+        "plantuml" => Some(recipe("brew", &["plantuml"], "brew uninstall plantuml 2>&1".to_string())),
+        "graphviz" => Some(recipe("brew", &["graphviz"], "brew uninstall graphviz 2>&1".to_string())),
+        "d2" => Some(recipe("brew", &["d2"], "brew uninstall d2 2>&1".to_string())),
+        _ => None,
+    }
+}
+
+// Get the install recipe for a dependency
+// Uses osascript on macOS for commands requiring admin privileges (shows native password dialog)
+// Uses pkexec on Linux for GUI sudo prompt
+pub fn install_recipe(name: &str, method: &str) -> Option<Recipe> {
+    const TEXLIVE_APT: &[&str] = &["texlive-latex-base", "texlive-fonts-recommended", "texlive-latex-extra"];
+
+    match (name, method) {
+        ("tectonic", "brew") => Some(recipe("brew", &["tectonic"], "brew install tectonic 2>&1".to_string())
+            .dry_run("brew install --dry-run tectonic 2>&1".to_string())),
+        ("tectonic", "cargo") => Some(recipe("cargo", &["tectonic"], "cargo install tectonic 2>&1".to_string())),
+        ("texlive", "brew") => {
+            let dry_run = "brew install --cask --dry-run basictex 2>&1".to_string();
+            if cfg!(target_os = "macos") {
+                // brew cask installs need sudo for the pkg installer
+                let command = with_askpass("install BasicTeX", "brew install --cask basictex 2>&1");
+                Some(recipe("brew", &["basictex"], command).elevated(Privilege::Required, "osascript").dry_run(dry_run))
+            } else {
+                Some(recipe("brew", &["basictex"], "brew install --cask basictex 2>&1".to_string()).dry_run(dry_run))
+            }
+        },
+        ("texlive", "apt") => {
+            let dry_run = format!("apt-get -s install {} 2>&1", TEXLIVE_APT.join(" "));
+            if cfg!(target_os = "linux") {
+                // Use pkexec for GUI password prompt on Linux
+                let command = format!("pkexec apt install -y {} 2>&1", TEXLIVE_APT.join(" "));
+                Some(recipe("apt", TEXLIVE_APT, command).elevated(Privilege::Required, "pkexec").dry_run(dry_run))
+            } else {
+                let command = format!("sudo apt install {} 2>&1", TEXLIVE_APT.join(" "));
+                Some(recipe("apt", TEXLIVE_APT, command).elevated(Privilege::Required, "sudo").dry_run(dry_run))
+            }
+        },
+        ("mermaid-filter", "npm") => {
+            let packages = &["mermaid-filter"];
+            let dry_run = "npm install -g --dry-run mermaid-filter 2>&1".to_string();
+            if cfg!(target_os = "macos") {
+                // Try without sudo first (works if npm prefix is user-writable), fall back to sudo with askpass
+                let command = format!("npm install -g mermaid-filter 2>&1 || ({})", with_askpass("install mermaid-filter", "sudo -A npm install -g mermaid-filter 2>&1"));
+                Some(recipe("npm", packages, command).elevated(Privilege::Fallback, "osascript").dry_run(dry_run))
+            } else if cfg!(target_os = "linux") {
+                Some(recipe("npm", packages, "npm install -g mermaid-filter 2>&1 || pkexec npm install -g mermaid-filter 2>&1".to_string())
+                    .elevated(Privilege::Fallback, "pkexec")
+                    .dry_run(dry_run))
+            } else {
+                Some(recipe("npm", packages, "npm install -g mermaid-filter 2>&1".to_string()).dry_run(dry_run))
+            }
+        },
+        ("pandoc-crossref", "brew") => Some(recipe("brew", &["pandoc-crossref"], "brew install pandoc-crossref 2>&1".to_string())
+            .dry_run("brew install --dry-run pandoc-crossref 2>&1".to_string())),
+        ("pandoc", "brew") => Some(recipe("brew", &["pandoc"], "brew install pandoc 2>&1".to_string())
+            .dry_run("brew install --dry-run pandoc 2>&1".to_string())),
+        ("plantuml", "brew") => Some(recipe("brew", &["plantuml"], "brew install plantuml 2>&1".to_string())
+            .dry_run("brew install --dry-run plantuml 2>&1".to_string())),
+        ("plantuml", "apt") => Some(apt_recipe("install", &["plantuml"])),
+        ("graphviz", "brew") => Some(recipe("brew", &["graphviz"], "brew install graphviz 2>&1".to_string())
+            .dry_run("brew install --dry-run graphviz 2>&1".to_string())),
+        ("graphviz", "apt") => Some(apt_recipe("install", &["graphviz"])),
+        ("d2", "brew") => Some(recipe("brew", &["d2"], "brew install d2 2>&1".to_string())
+            .dry_run("brew install --dry-run d2 2>&1".to_string())),
+        _ => None,
+    }
+}
+
+// Install methods the app knows for each dependency
+pub fn install_methods(name: &str) -> &'static [&'static str] {
+    match name {
+        "tectonic" => &["brew", "cargo"],
+        "texlive" => &["brew", "apt"],
+        "mermaid-filter" => &["npm"],
+        "pandoc-crossref" => &["brew"],
+        "pandoc" => &["brew"],
+        "plantuml" | "graphviz" => &["brew", "apt"],
+        "d2" => &["brew"],
+        _ => &[],
+    }
+}
+
+// Program and arguments that prove a dependency is installed and runnable
+// mermaid-filter reads stdin and has no --version, so it is only resolved on PATH
+fn detection_command(name: &str) -> Option<(&'static str, &'static [&'static str])> {
+    match name {
+        "pandoc" => Some(("pandoc", &["--version"])),
+        "tectonic" => Some(("tectonic", &["--version"])),
+        "texlive" => Some(("pdflatex", &["--version"])),
+        "mermaid-filter" => Some(("mermaid-filter", &[])),
+        "pandoc-crossref" => Some(("pandoc-crossref", &["--version"])),
+        "plantuml" => Some(("plantuml", &["-version"])),
+        "graphviz" => Some(("dot", &["-V"])),
+        "d2" => Some(("d2", &["--version"])),
+        _ => None,
+    }
+}
+
+// Find an executable on the extended PATH
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let separator = if cfg!(target_os = "windows") { ';' } else { ':' };
+    let extensions: &[&str] = if cfg!(target_os = "windows") { &["", ".exe", ".cmd", ".bat"] } else { &[""] };
+
+    get_extended_path()
+        .split(separator)
+        .filter(|dir| !dir.is_empty())
+        .flat_map(|dir| extensions.iter().map(move |ext| Path::new(dir).join(format!("{}{}", program, ext))))
+        .find(|candidate| candidate.is_file())
+}
+
+// Run a dependency's detection command and return the first line of its output
+pub fn detect(name: &str) -> Result<String, String> {
+    let (program, args) = detection_command(name)
+        .ok_or_else(|| format!("Unknown dependency: {}", name))?;
+
+    let resolved = find_in_path(program)
+        .ok_or_else(|| format!("{} not found on PATH", program))?;
+
+    if args.is_empty() {
+        return Ok(resolved.to_string_lossy().to_string());
+    }
+
+    let output = Command::new(&resolved)
+        .args(args)
+        .env("PATH", get_extended_path())
+        .output()
+        .map_err(|e| format!("Failed to execute: {}", e))?;
+
+    if output.status.success() {
+        // dot -V prints its version on stderr
+        let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+        Ok(String::from_utf8_lossy(&text).lines().next().unwrap_or_default().trim().to_string())
+    } else {
+        Err(format!("Command failed: {}", String::from_utf8_lossy(&output.stderr)))
+    }
+}
+
+// Pull the list of would-be changes out of a package manager's dry-run output
+// apt -s prints "Inst pkg (...)"/"Remv pkg [...]", brew prints "==> Would install N formulae:" followed by names
+fn parse_dry_run_changes(manager: &str, output: &str) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    match manager {
+        "apt" => {
+            for line in output.lines() {
+                let mut parts = line.split_whitespace();
+                let action = match parts.next() {
+                    Some("Inst") => "install",
+                    Some("Remv") => "remove",
+                    Some("Purg") => "purge",
+                    _ => continue,
+                };
+                if let Some(package) = parts.next() {
+                    changes.push(format!("{} {}", action, package));
+                }
+            }
+        },
+        "brew" => {
+            let mut action: Option<String> = None;
+            for line in output.lines() {
+                let line = line.trim();
+                if let Some(header) = line.strip_prefix("==> ") {
+                    action = header.strip_prefix("Would ")
+                        .and_then(|rest| rest.split_whitespace().next())
+                        .map(|verb| verb.to_string());
+                } else if let Some(verb) = &action {
+                    for package in line.split_whitespace() {
+                        changes.push(format!("{} {}", verb, package));
+                    }
+                }
+            }
+        },
+        "npm" => {
+            for line in output.lines() {
+                let line = line.trim();
+                if line.starts_with("add ") || line.starts_with("remove ") || line.starts_with("change ")
+                    || line.starts_with("added ") || line.starts_with("removed ") || line.starts_with("changed ")
+                {
+                    changes.push(line.to_string());
+                }
+            }
+        },
+        _ => {},
+    }
+
+    changes
+}
+
+// Run the recipe's native dry-run command (never escalates) and record what it reports
+fn plan_step(operation: String, recipe: Recipe) -> PlannedStep {
+    let dry_run_output = recipe.dry_run_command.as_ref().map(|command| {
+        let output = if cfg!(target_os = "windows") {
+            Command::new("cmd").args(["/C", command]).output()
+        } else {
+            Command::new("sh")
+                .args(["-c", command])
+                .env("PATH", get_extended_path())
+                .output()
+        };
+
+        match output {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+            Err(e) => format!("Failed to execute: {}", e),
+        }
+    });
+
+    let changes = dry_run_output
+        .as_deref()
+        .map(|output| parse_dry_run_changes(recipe.manager, output))
+        .unwrap_or_default();
+
+    let native_dry_run = recipe.dry_run_command.is_some();
+    PlannedStep { operation, recipe, dry_run_output, changes, native_dry_run }
+}
+
+async fn plan(name: String, steps: Vec<(String, Recipe)>) -> Result<DependencyOutcome, String> {
+    let steps = tokio::task::spawn_blocking(move || {
+        steps.into_iter()
+            .map(|(operation, recipe)| plan_step(operation, recipe))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?;
+
+    let commands_only = steps.iter().any(|step| !step.native_dry_run);
+    Ok(DependencyOutcome::Planned(DryRunPlan { name, steps, commands_only }))
+}
+
+#[tauri::command]
+pub async fn install_dependency(app: AppHandle, name: String, method: String, dry_run: Option<bool>) -> Result<DependencyOutcome, String> {
+    let recipe = install_recipe(&name, &method)
+        .ok_or_else(|| format!("Unknown install method {} for {}", method, name))?;
+
+    if dry_run.unwrap_or(false) {
+        info!("Planning install of {} via {}", name, method);
+        return plan(name.clone(), vec![(format!("Installing {}", name), recipe)]).await;
+    }
+
+    info!("Installing dependency: {} via {}", name, method);
+    info!("Install command: {}", recipe.command);
+
+    run_command_with_output(app, recipe.command, format!("Installing {}", name)).await
+        .map(DependencyOutcome::Completed)
+}
+
+#[tauri::command]
+pub async fn uninstall_dependency(app: AppHandle, name: String, dry_run: Option<bool>) -> Result<DependencyOutcome, String> {
+    let recipe = uninstall_recipe(&name)
+        .ok_or_else(|| format!("Unknown dependency: {}", name))?;
+
+    if dry_run.unwrap_or(false) {
+        info!("Planning uninstall of {}", name);
+        return plan(name.clone(), vec![(format!("Uninstalling {}", name), recipe)]).await;
+    }
+
+    info!("Uninstalling dependency: {}", name);
+    info!("Uninstall command: {}", recipe.command);
+
+    run_command_with_output(app, recipe.command, format!("Uninstalling {}", name)).await
+        .map(DependencyOutcome::Completed)
+}
+
+// Emit a reinstall phase marker on the command-output channel
+pub fn emit_phase(app: &AppHandle, phase: &str, status: &str, message: String) {
+    let _ = app.emit("command-output", serde_json::json!({
+        "type": "phase",
+        "phase": phase,
+        "status": status,
+        "message": message
+    }));
+}
+
+async fn detect_async(name: String) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || detect(&name))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+// Only known dependency names are accepted, so the webview can't run arbitrary commands
+#[tauri::command]
+pub async fn detect_dependency(name: String) -> Result<String, String> {
+    detect_async(name).await
+}
+
+#[tauri::command]
+pub async fn reinstall_dependency(app: AppHandle, name: String, method: String, dry_run: Option<bool>) -> Result<DependencyOutcome, String> {
+    let uninstall = uninstall_recipe(&name)
+        .ok_or_else(|| format!("Unknown dependency: {}", name))?;
+    let install = install_recipe(&name, &method)
+        .ok_or_else(|| format!("Unknown install method {} for {}", method, name))?;
+
+    if dry_run.unwrap_or(false) {
+        info!("Planning reinstall of {} via {}", name, method);
+        return plan(name.clone(), vec![
+            (format!("Uninstalling {}", name), uninstall),
+            (format!("Reinstalling {}", name), install),
+        ]).await;
+    }
+
+    let cancel_mark = cancel_requests();
+    let cancelled = || cancel_requests() != cancel_mark;
+
+    // Remember whether the tool worked before we touch it, so we know whether to reinstall after a failure
+    let previous = detect_async(name.clone()).await.ok();
+    if let Some(version) = &previous {
+        emit_phase(&app, "check", "success", format!("Current {}: {}", name, version));
+    } else {
+        emit_phase(&app, "check", "failed", format!("{} is not currently working", name));
+    }
+
+    emit_phase(&app, "uninstall", "start", format!("Uninstalling {}", name));
+    match run_command_with_output(app.clone(), uninstall.command, format!("Uninstalling {}", name)).await {
+        Ok(message) => emit_phase(&app, "uninstall", "success", message),
+        Err(e) if e == "Operation cancelled" => {
+            emit_phase(&app, "uninstall", "failed", e.clone());
+            return Err(e);
+        },
+        Err(e) if previous.is_some() => {
+            // Nothing was removed, so stop here rather than installing over a working tool
+            emit_phase(&app, "uninstall", "failed", e.clone());
+            return Err(format!("Reinstall aborted, {} was left unchanged: {}", name, e));
+        },
+        Err(e) => emit_phase(&app, "uninstall", "failed", format!("{} (continuing, {} was not working)", e, name)),
+    }
+
+    // A cancel while the uninstall was finishing must not go on to the install
+    if cancelled() {
+        emit_phase(&app, "install", "skipped", "Operation cancelled".to_string());
+        return Err("Operation cancelled".to_string());
+    }
+
+    emit_phase(&app, "install", "start", format!("Reinstalling {} via {}", name, method));
+    let installed = run_command_with_output(app.clone(), install.command, format!("Reinstalling {}", name)).await;
+    match &installed {
+        Ok(message) => emit_phase(&app, "install", "success", message.clone()),
+        Err(e) if e == "Operation cancelled" => {
+            emit_phase(&app, "install", "failed", e.clone());
+            return Err(e.clone());
+        },
+        Err(e) => emit_phase(&app, "install", "failed", e.clone()),
+    }
+
+    // Confirm the new install actually works, not just that the package manager exited cleanly
+    emit_phase(&app, "verify", "start", format!("Verifying {}", name));
+    let failure = match (installed, detect_async(name.clone()).await) {
+        (Ok(_), Ok(version)) => {
+            emit_phase(&app, "verify", "success", format!("{} is working: {}", name, version));
+            return Ok(DependencyOutcome::Completed(format!("Reinstalling {} completed successfully", name)));
+        },
+        (Ok(_), Err(e)) => {
+            emit_phase(&app, "verify", "failed", e.clone());
+            format!("{} was installed but does not run: {}", name, e)
+        },
+        (Err(e), _) => {
+            emit_phase(&app, "verify", "skipped", format!("Install of {} failed", name));
+            e
+        },
+    };
+
+    if previous.is_none() {
+        return Err(failure);
+    }
+
+    // The tool worked before, so try to get a working install back, leaving the failed method for last.
+    // This installs whatever the package manager currently serves; the previous version isn't pinned
+    let mut methods: Vec<&str> = install_methods(&name).iter().copied().filter(|m| *m != method).collect();
+    methods.push(&method);
+
+    for fallback in methods {
+        if cancelled() {
+            emit_phase(&app, "rollback", "skipped", "Operation cancelled".to_string());
+            return Err(format!("{}; cancelled before {} could be reinstalled", failure, name));
+        }
+        let Some(recipe) = install_recipe(&name, fallback) else { continue };
+        emit_phase(&app, "rollback", "start", format!("Reinstalling {} via {}", name, fallback));
+        if let Err(e) = run_command_with_output(app.clone(), recipe.command, format!("Reinstalling {}", name)).await {
+            emit_phase(&app, "rollback", "failed", e);
+            continue;
+        }
+        if let Ok(version) = detect_async(name.clone()).await {
+            emit_phase(&app, "rollback", "success", format!("Reinstalled {}: {}", name, version));
+            return Err(format!("{}; {} was reinstalled via {} ({}), which may differ from the previous version", failure, name, fallback, version));
+        }
+        emit_phase(&app, "rollback", "failed", format!("{} still does not run after reinstalling via {}", name, fallback));
+    }
+
+    Err(format!("{}; could not get a working {} back", failure, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apt_simulation() {
+        let output = "Reading package lists...\nInst pandoc (3.1.3+ds-2 Debian:12/stable [amd64])\nRemv old-pandoc [2.9]\nPurg cfg-pkg [1.0]\nConf pandoc (3.1.3+ds-2)\n";
+        assert_eq!(parse_dry_run_changes("apt", output), vec!["install pandoc", "remove old-pandoc", "purge cfg-pkg"]);
+    }
+
+    #[test]
+    fn brew_dry_run() {
+        let output = "==> Would install 2 formulae:\npandoc librsvg\n==> Would upgrade 1 outdated package:\ncairo\n==> Downloading https://example.org\nfile.tar.gz\n";
+        assert_eq!(parse_dry_run_changes("brew", output), vec!["install pandoc", "install librsvg", "upgrade cairo"]);
+    }
+
+    #[test]
+    fn npm_dry_run() {
+        let output = "npm WARN config\nadd @mermaid-js/mermaid-cli 10.9.1\nadded 312 packages in 4s\n";
+        assert_eq!(parse_dry_run_changes("npm", output), vec!["add @mermaid-js/mermaid-cli 10.9.1", "added 312 packages in 4s"]);
+    }
+
+    #[test]
+    fn unknown_managers_report_nothing() {
+        assert!(parse_dry_run_changes("winget", "Inst pandoc").is_empty());
+    }
+}
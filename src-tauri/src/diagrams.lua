@@ -0,0 +1,131 @@
+-- Renders mermaid, PlantUML, Graphviz and D2 code blocks to images.
+-- Written into the job workspace by run_pandoc, which passes the renderer paths, image formats,
+-- settings fingerprints and cache folder as PANDOC_GUI_* environment variables.
+-- Renders are cached by a hash of renderer, format, settings and source and reused across conversions.
+
+local cache_dir = os.getenv("PANDOC_GUI_DIAGRAM_CACHE")
+
+-- Temporary files in the shared cache carry the job's name, so two conversions rendering the same
+-- diagram at once never write to the same file
+local job_dir = os.getenv("PANDOC_GUI_JOB_DIR")
+local job_id = job_dir and pandoc.path.filename(job_dir) or tostring(os.time()) .. "-" .. tostring(math.random(1e9))
+
+local function read_file(path)
+  local file = io.open(path, "rb")
+  if not file then
+    return nil
+  end
+  local content = file:read("a")
+  file:close()
+  return content
+end
+
+local function write_file(path, content)
+  local file = assert(io.open(path, "wb"))
+  file:write(content)
+  file:close()
+end
+
+local renderers = {
+  mermaid = {
+    classes = { "mermaid" },
+    -- mermaid-filter is a JSON filter: run it on a document holding only this diagram and keep
+    -- the image it writes (into MERMAID_FILTER_LOC, using the job's config and MERMAID_FILTER_* settings)
+    render = function(program, _, source)
+      local doc = pandoc.Pandoc({ pandoc.CodeBlock(source, pandoc.Attr("", { "mermaid" })) })
+      local rendered = pandoc.read(pandoc.pipe(program, { FORMAT }, pandoc.write(doc, "json")), "json")
+      local src
+      rendered:walk({ Image = function(image) src = src or image.src end })
+      if not src then
+        error("mermaid-filter returned no image")
+      end
+      return read_file(src)
+    end,
+  },
+  plantuml = {
+    classes = { "plantuml", "puml" },
+    render = function(program, format, source)
+      return pandoc.pipe(program, { "-t" .. format, "-pipe", "-charset", "UTF-8" }, source)
+    end,
+  },
+  graphviz = {
+    classes = { "dot", "graphviz" },
+    render = function(program, format, source)
+      return pandoc.pipe(program, { "-T" .. format }, source)
+    end,
+  },
+  d2 = {
+    classes = { "d2" },
+    -- d2 can't write PNG/PDF to stdout, so it goes through job-specific files next to the cache entry
+    render = function(program, format, source, scratch)
+      local input = scratch .. ".d2"
+      local output = scratch .. ".tmp." .. format
+      write_file(input, source)
+      pandoc.pipe(program, { input, output }, "")
+      local content = read_file(output)
+      os.remove(input)
+      os.remove(output)
+      return content
+    end,
+  },
+}
+
+-- Renderer name for a code block, if one of its classes is a diagram language this job renders
+local function renderer_for(block)
+  for name, renderer in pairs(renderers) do
+    if os.getenv("PANDOC_GUI_" .. name:upper()) then
+      for _, class in ipairs(renderer.classes) do
+        if block.classes:includes(class) then
+          return name
+        end
+      end
+    end
+  end
+  return nil
+end
+
+function CodeBlock(block)
+  local name = renderer_for(block)
+  if not name then
+    return nil
+  end
+
+  local program = os.getenv("PANDOC_GUI_" .. name:upper())
+  local format = os.getenv("PANDOC_GUI_" .. name:upper() .. "_FORMAT") or "svg"
+  local variant = os.getenv("PANDOC_GUI_" .. name:upper() .. "_VARIANT") or ""
+  local hash = pandoc.utils.sha1(name .. "\0" .. format .. "\0" .. variant .. "\0" .. block.text)
+  local base = pandoc.path.join({ cache_dir, hash })
+  local path = base .. "." .. format
+
+  if not read_file(path) then
+    local scratch = base .. "." .. job_id
+    local ok, content = pcall(renderers[name].render, program, format, block.text, scratch)
+    if not ok or not content or content == "" then
+      error(name .. " failed to render a diagram: " .. tostring(content))
+    end
+    -- Write a job-specific file then rename it into place, so a concurrent conversion never reads
+    -- or writes a half-written image
+    local partial = scratch .. ".partial." .. format
+    write_file(partial, content)
+    -- On Windows rename fails when another job got there first; its image is just as good
+    if not os.rename(partial, path) then
+      os.remove(partial)
+    end
+  end
+
+  local size = pandoc.Attr("", {}, { width = block.attributes.width, height = block.attributes.height })
+  local caption = block.attributes.caption
+  if caption and caption ~= "" then
+    local inlines = pandoc.utils.blocks_to_inlines(pandoc.read(caption, "markdown").blocks)
+    local image = pandoc.Image(inlines, path, "", size)
+    if pandoc.Figure then
+      return pandoc.Figure(pandoc.Plain({ image }), { pandoc.Plain(inlines) }, pandoc.Attr(block.identifier))
+    end
+    -- pandoc 2.x: an image alone in a paragraph with a "fig:" title becomes a figure
+    image.title = "fig:"
+    image.identifier = block.identifier
+    return pandoc.Para({ image })
+  end
+  size.identifier = block.identifier
+  return pandoc.Para({ pandoc.Image({}, path, "", size) })
+end
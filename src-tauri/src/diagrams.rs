@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::info;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::deps::find_in_path;
+use crate::mermaid;
+use crate::workspace::JobWorkspace;
+
+// Lua filter that renders diagram code blocks (mermaid included); written into each job workspace
+const FILTER: &str = include_str!("diagrams.lua");
+
+// Rendered diagrams are kept here, named by a hash of renderer, format, settings and source
+const CACHE_DIR: &str = "diagrams";
+
+// A diagram language the filter can render: dependency name, program and image formats it can write
+struct Renderer {
+    name: &'static str,
+    program: &'static str,
+    formats: &'static [&'static str],
+}
+
+// PlantUML's PDF output needs extra jars that most installs lack, so LaTeX gets PNG instead
+const RENDERERS: &[Renderer] = &[
+    Renderer { name: "plantuml", program: "plantuml", formats: &["svg", "png"] },
+    Renderer { name: "graphviz", program: "dot", formats: &["svg", "pdf", "png"] },
+    Renderer { name: "d2", program: "d2", formats: &["svg", "pdf", "png"] },
+];
+
+// Same choice as mermaid (PDF for LaTeX, SVG for HTML/EPUB, PNG for office formats), falling
+// back to PNG when the renderer can't write that format
+fn image_format(renderer: &Renderer, target: &str) -> &'static str {
+    let preferred = mermaid::image_format(None, target);
+    if renderer.formats.contains(&preferred) {
+        preferred
+    } else {
+        "png"
+    }
+}
+
+pub fn cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(CACHE_DIR))
+        .map_err(|e| format!("Could not find app cache directory: {}", e))
+}
+
+// The Lua filter written into a job workspace plus the variables it reads: the cache folder, then
+// each renderer's program path, image format and settings fingerprint (PANDOC_GUI_GRAPHVIZ, ..._FORMAT)
+pub struct DiagramFilter {
+    pub path: PathBuf,
+    pub vars: Vec<(String, String)>,
+}
+
+impl DiagramFilter {
+    pub fn create(workspace: &JobWorkspace, cache_dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(cache_dir)
+            .map_err(|e| format!("Failed to create {}: {}", cache_dir.display(), e))?;
+        let path = workspace.write("diagrams.lua", FILTER)?;
+        let vars = vec![("PANDOC_GUI_DIAGRAM_CACHE".to_string(), cache_dir.to_string_lossy().to_string())];
+        Ok(DiagramFilter { path, vars })
+    }
+
+    // Render one language with the given program; the variant covers settings other than the
+    // source and format that change the output, so cached renders follow settings changes
+    pub fn add(&mut self, name: &str, program: &Path, format: &str, variant: &str) {
+        let key = format!("PANDOC_GUI_{}", name.to_uppercase());
+        self.vars.push((format!("{}_FORMAT", key), format.to_string()));
+        self.vars.push((format!("{}_VARIANT", key), variant.to_string()));
+        self.vars.push((key, program.to_string_lossy().to_string()));
+    }
+
+    // PlantUML, Graphviz or D2, resolved on PATH
+    pub fn add_language(&mut self, language: &str, target: &str) -> Result<(), String> {
+        let renderer = RENDERERS.iter()
+            .find(|r| r.name == language)
+            .ok_or_else(|| format!("Unknown diagram language: {}", language))?;
+        let program = find_in_path(renderer.program).ok_or_else(|| format!(
+            "The document has {} diagrams but {} was not found on PATH. Install it from Check Dependencies.",
+            renderer.name, renderer.program
+        ))?;
+        self.add(renderer.name, &program, image_format(renderer, target), "");
+        Ok(())
+    }
+}
+
+// Total size of the rendered diagram cache
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheInfo {
+    pub path: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+fn cache_info(dir: &Path) -> CacheInfo {
+    let mut info = CacheInfo { path: dir.to_string_lossy().to_string(), files: 0, bytes: 0 };
+    if let Ok(entries) = fs::read_dir(dir) {
+        for metadata in entries.flatten().filter_map(|entry| entry.metadata().ok()) {
+            if metadata.is_file() {
+                info.files += 1;
+                info.bytes += metadata.len();
+            }
+        }
+    }
+    info
+}
+
+#[tauri::command]
+pub async fn get_diagram_cache_size(app: AppHandle) -> Result<CacheInfo, String> {
+    let dir = cache_dir(&app)?;
+    tokio::task::spawn_blocking(move || cache_info(&dir))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+// Delete every cached render and return what was freed; diagrams are re-rendered on the next conversion
+#[tauri::command]
+pub async fn clear_diagram_cache(app: AppHandle) -> Result<CacheInfo, String> {
+    let dir = cache_dir(&app)?;
+    tokio::task::spawn_blocking(move || {
+        let freed = cache_info(&dir);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)
+                .map_err(|e| format!("Failed to clear diagram cache {}: {}", dir.display(), e))?;
+        }
+        info!("Cleared diagram cache: {} files, {} bytes", freed.files, freed.bytes);
+        Ok(freed)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
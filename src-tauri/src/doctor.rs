@@ -0,0 +1,295 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::deps::find_in_path;
+use crate::font_cache::cached_families;
+use crate::get_extended_path;
+
+// LaTeX packages the generated headers rely on (fancyhdr/lastpage/underscore for page
+// styles, fontspec for custom fonts, pagecolor for dark mode)
+const LATEX_PACKAGES: &[&str] = &["fancyhdr", "lastpage", "underscore", "fontspec", "pagecolor"];
+
+// Fonts the default settings and Unicode fallback header refer to
+const DEFAULT_FONTS: &[&str] = &["Latin Modern Roman", "Latin Modern Mono", "Noto Mono", "DejaVu Sans Mono", "Menlo", "Apple Symbols"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Missing,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DoctorSection {
+    pub title: String,
+    pub checks: Vec<DoctorCheck>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorReport {
+    pub app_version: String,
+    pub platform: String,
+    pub sections: Vec<DoctorSection>,
+}
+
+fn check(name: &str, status: CheckStatus, detail: impl Into<String>) -> DoctorCheck {
+    DoctorCheck { name: name.to_string(), status, detail: detail.into() }
+}
+
+// Resolve a program on the extended PATH and return its path plus the first line of `program args`
+fn probe(program: &str, args: &[&str]) -> Result<(String, String), String> {
+    let resolved = find_in_path(program)
+        .ok_or_else(|| format!("{} not found on PATH", program))?;
+
+    let output = Command::new(&resolved)
+        .args(args)
+        .env("PATH", get_extended_path())
+        .output()
+        .map_err(|e| format!("Failed to execute {}: {}", resolved.display(), e))?;
+
+    // Some tools (tlmgr, older node) print their version on stderr
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    let first_line = String::from_utf8_lossy(&text).lines().next().unwrap_or_default().trim().to_string();
+
+    if output.status.success() {
+        Ok((resolved.to_string_lossy().to_string(), first_line))
+    } else {
+        Err(format!("{} exited with {:?}: {}", resolved.display(), output.status.code(), first_line))
+    }
+}
+
+fn tool_check(name: &str, program: &str, args: &[&str], required: bool) -> DoctorCheck {
+    match probe(program, args) {
+        Ok((path, version)) => check(name, CheckStatus::Ok, format!("{} ({})", version, path)),
+        Err(e) if required => check(name, CheckStatus::Missing, e),
+        Err(e) => check(name, CheckStatus::Warning, e),
+    }
+}
+
+fn pandoc_section() -> DoctorSection {
+    DoctorSection {
+        title: "Pandoc".to_string(),
+        checks: vec![
+            tool_check("pandoc", "pandoc", &["--version"], true),
+            tool_check("pandoc-crossref", "pandoc-crossref", &["--version"], false),
+        ],
+    }
+}
+
+fn engines_section() -> DoctorSection {
+    DoctorSection {
+        title: "PDF engines".to_string(),
+        checks: ["tectonic", "pdflatex", "xelatex", "lualatex"]
+            .iter()
+            .map(|engine| tool_check(engine, engine, &["--version"], false))
+            .collect(),
+    }
+}
+
+fn tex_section() -> DoctorSection {
+    let mut checks = vec![
+        tool_check("TeX distribution", "tex", &["--version"], false),
+        tool_check("tlmgr", "tlmgr", &["--version"], false),
+    ];
+
+    let kpsewhich = find_in_path("kpsewhich");
+    for package in LATEX_PACKAGES {
+        let name = format!("{}.sty", package);
+        let found = kpsewhich.as_ref().and_then(|kpsewhich| {
+            Command::new(kpsewhich)
+                .arg(&name)
+                .env("PATH", get_extended_path())
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                .filter(|path| !path.is_empty())
+        });
+
+        checks.push(match (found, &kpsewhich) {
+            (Some(path), _) => check(&name, CheckStatus::Ok, path),
+            (None, Some(_)) => check(&name, CheckStatus::Missing, format!("Not installed (try: tlmgr install {})", package)),
+            (None, None) => check(&name, CheckStatus::Warning, "kpsewhich not found, cannot check"),
+        });
+    }
+
+    DoctorSection { title: "TeX packages".to_string(), checks }
+}
+
+fn fonts_section(app: &AppHandle) -> DoctorSection {
+    let installed: Vec<String> = cached_families(app)
+        .into_iter()
+        .map(|font| font.to_lowercase())
+        .collect();
+
+    let checks = if installed.is_empty() {
+        vec![check("font listing", CheckStatus::Warning, "Could not list system fonts (fc-list unavailable?)")]
+    } else {
+        DEFAULT_FONTS
+            .iter()
+            .map(|font| {
+                if installed.contains(&font.to_lowercase()) {
+                    check(font, CheckStatus::Ok, "Installed")
+                } else {
+                    check(font, CheckStatus::Warning, "Not installed")
+                }
+            })
+            .collect()
+    };
+
+    DoctorSection { title: "Fonts".to_string(), checks }
+}
+
+// Locate a Chromium build puppeteer can use: explicit env var, puppeteer's download cache,
+// or the copy bundled inside the global mermaid-filter install
+fn find_chromium(npm_root: Option<&str>) -> Option<String> {
+    if let Ok(path) = env::var("PUPPETEER_EXECUTABLE_PATH") {
+        if Path::new(&path).exists() {
+            return Some(path);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".cache").join("puppeteer"));
+    }
+    if let Some(root) = npm_root {
+        candidates.push(Path::new(root).join("mermaid-filter").join("node_modules").join("puppeteer").join(".local-chromium"));
+    }
+
+    candidates.into_iter()
+        .filter(|dir| dir.is_dir())
+        .find_map(|dir| {
+            std::fs::read_dir(&dir).ok()?
+                .flatten()
+                .find(|entry| entry.path().is_dir())
+                .map(|entry| entry.path().to_string_lossy().to_string())
+        })
+}
+
+fn mermaid_section() -> DoctorSection {
+    let mut checks = vec![
+        tool_check("node", "node", &["--version"], false),
+        tool_check("npm", "npm", &["--version"], false),
+    ];
+
+    checks.push(match find_in_path("mermaid-filter") {
+        Some(path) => check("mermaid-filter", CheckStatus::Ok, path.to_string_lossy()),
+        None => check("mermaid-filter", CheckStatus::Warning, "Not found on PATH"),
+    });
+
+    let npm_root = probe("npm", &["root", "-g"]).ok().map(|(_, root)| root);
+    if let Some(root) = &npm_root {
+        let puppeteer = Path::new(root).join("mermaid-filter").join("node_modules").join("puppeteer");
+        checks.push(if puppeteer.is_dir() {
+            check("puppeteer", CheckStatus::Ok, puppeteer.to_string_lossy())
+        } else {
+            check("puppeteer", CheckStatus::Warning, format!("Not found under {}", root))
+        });
+    }
+
+    checks.push(match find_chromium(npm_root.as_deref()) {
+        Some(path) => check("Chromium", CheckStatus::Ok, path),
+        None => check("Chromium", CheckStatus::Warning, "No puppeteer Chromium download found; set PUPPETEER_EXECUTABLE_PATH to use an installed browser"),
+    });
+
+    DoctorSection { title: "Mermaid".to_string(), checks }
+}
+
+fn diagrams_section() -> DoctorSection {
+    DoctorSection {
+        title: "Diagram renderers".to_string(),
+        checks: vec![
+            tool_check("plantuml", "plantuml", &["-version"], false),
+            tool_check("graphviz", "dot", &["-V"], false),
+            tool_check("d2", "d2", &["--version"], false),
+        ],
+    }
+}
+
+fn path_section() -> DoctorSection {
+    let separator = if cfg!(target_os = "windows") { ';' } else { ':' };
+    let checks = get_extended_path()
+        .split(separator)
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| {
+            if Path::new(dir).is_dir() {
+                check(dir, CheckStatus::Ok, "exists")
+            } else {
+                check(dir, CheckStatus::Warning, "does not exist")
+            }
+        })
+        .collect();
+
+    DoctorSection { title: "PATH (search order)".to_string(), checks }
+}
+
+pub fn build_report(app: &AppHandle) -> DoctorReport {
+    DoctorReport {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        platform: format!("{} {}", env::consts::OS, env::consts::ARCH),
+        sections: vec![
+            pandoc_section(),
+            engines_section(),
+            tex_section(),
+            fonts_section(app),
+            mermaid_section(),
+            diagrams_section(),
+            path_section(),
+        ],
+    }
+}
+
+impl DoctorReport {
+    // Render as Markdown for pasting into bug reports
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "# Pandoc GUI doctor report\n\n- App version: {}\n- Platform: {}\n",
+            self.app_version, self.platform
+        );
+
+        for section in &self.sections {
+            out.push_str(&format!("\n## {}\n\n| Check | Status | Detail |\n|---|---|---|\n", section.title));
+            for item in &section.checks {
+                let status = match item.status {
+                    CheckStatus::Ok => "ok",
+                    CheckStatus::Warning => "warning",
+                    CheckStatus::Missing => "missing",
+                };
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    item.name.replace('|', "\\|"),
+                    status,
+                    item.detail.replace('|', "\\|").replace('\n', " ")
+                ));
+            }
+        }
+
+        out
+    }
+}
+
+#[tauri::command]
+pub async fn run_doctor(app: AppHandle) -> Result<DoctorReport, String> {
+    tokio::task::spawn_blocking(move || build_report(&app))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
+
+// Format a report the frontend got from run_doctor, so copying it doesn't run every probe again
+#[tauri::command]
+pub fn doctor_report_markdown(report: DoctorReport) -> String {
+    report.to_markdown()
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::workspace::JobWorkspace;
+use crate::{slug, unique_id};
+
+// Registered user filters live next to the app's other data as a JSON array
+const FILTERS_FILE: &str = "filters.json";
+
+// Filters shipped with the app: id, name, description and source
+const BUILTIN_FILTERS: &[(&str, &str, &str, &str)] = &[
+    ("pagebreak", "Page Breaks", "\\newpage or <!-- pagebreak --> becomes a page break in PDF, Word, ODT and HTML", include_str!("filters/pagebreak.lua")),
+    ("include-files", "Include Files", "```include blocks are replaced by the listed files", include_str!("filters/include-files.lua")),
+    ("landscape", "Landscape Sections", "::: landscape divs are typeset on rotated pages in PDF", include_str!("filters/landscape.lua")),
+    ("admonitions", "Admonitions", "::: note/tip/warning divs and GitHub alerts get a titled box", include_str!("filters/admonitions.lua")),
+];
+
+// A Lua filter the user registered from a file; the file stays where it is so edits apply to the next conversion
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LuaFilter {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterListing {
+    #[serde(flatten)]
+    pub filter: LuaFilter,
+    pub builtin: bool,
+    // False when a registered file has been moved or deleted
+    pub available: bool,
+}
+
+fn filters_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(FILTERS_FILE))
+        .map_err(|e| format!("Could not find app data directory: {}", e))
+}
+
+fn load_user_filters(app: &AppHandle) -> Result<Vec<LuaFilter>, String> {
+    let path = filters_path(app)?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_user_filters(app: &AppHandle, filters: &[LuaFilter]) -> Result<(), String> {
+    let path = filters_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(filters)
+        .map_err(|e| format!("Failed to serialize filters: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Leading `--` comment lines of a Lua file, joined, as its description
+fn describe(source: &str) -> String {
+    source
+        .lines()
+        .map_while(|line| line.trim().strip_prefix("--"))
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn builtin_listing(id: &str, name: &str, description: &str) -> FilterListing {
+    FilterListing {
+        filter: LuaFilter {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            path: String::new(),
+        },
+        builtin: true,
+        available: true,
+    }
+}
+
+// Paths for the enabled filters in the preset's order: built-ins are written into the workspace,
+// registered filters are used in place
+pub fn resolve(app: &AppHandle, ids: &[String], workspace: &JobWorkspace) -> Result<Vec<PathBuf>, String> {
+    let user = load_user_filters(app)?;
+    let mut paths = Vec::new();
+
+    for id in ids {
+        if let Some((_, _, _, source)) = BUILTIN_FILTERS.iter().find(|(builtin, ..)| builtin == id) {
+            paths.push(workspace.write(&format!("filter-{}.lua", id), source)?);
+            continue;
+        }
+
+        let filter = user.iter()
+            .find(|f| &f.id == id)
+            .ok_or_else(|| format!("Unknown Lua filter '{}': it may have been removed from the filter list", id))?;
+        let path = Path::new(&filter.path);
+        if !path.is_file() {
+            return Err(format!("Lua filter '{}' not found at {}", filter.name, path.display()));
+        }
+        paths.push(path.to_path_buf());
+    }
+
+    Ok(paths)
+}
+
+#[tauri::command]
+pub fn list_lua_filters(app: AppHandle) -> Result<Vec<FilterListing>, String> {
+    let mut listings: Vec<FilterListing> = BUILTIN_FILTERS.iter()
+        .map(|(id, name, description, _)| builtin_listing(id, name, description))
+        .collect();
+    listings.extend(load_user_filters(&app)?.into_iter().map(|filter| FilterListing {
+        available: Path::new(&filter.path).is_file(),
+        builtin: false,
+        filter,
+    }));
+    Ok(listings)
+}
+
+// Pick a .lua file and register it; only files chosen in the dialog can become filters, since
+// filters run arbitrary code during conversion
+#[tauri::command]
+pub async fn add_lua_filter(app: AppHandle) -> Result<Option<LuaFilter>, String> {
+    let picked = app.dialog()
+        .file()
+        .add_filter("Lua filters", &["lua"])
+        .blocking_pick_file();
+
+    let Some(picked) = picked else { return Ok(None) };
+    let path = picked.into_path()
+        .map_err(|e| format!("Invalid file selection: {}", e))?;
+    let path = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+    let source = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut user = load_user_filters(&app)?;
+    if let Some(existing) = user.iter().find(|f| Path::new(&f.path) == path) {
+        warn!("Lua filter already registered: {}", path.display());
+        return Ok(Some(existing.clone()));
+    }
+
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let id = unique_id(&slug(&stem, "filter"), |id| {
+        BUILTIN_FILTERS.iter().any(|(builtin, ..)| *builtin == id) || user.iter().any(|f| f.id == id)
+    });
+
+    let filter = LuaFilter {
+        id,
+        name: stem,
+        description: describe(&source),
+        path: path.to_string_lossy().to_string(),
+    };
+    user.push(filter.clone());
+    save_user_filters(&app, &user)?;
+    Ok(Some(filter))
+}
+
+#[tauri::command]
+pub fn remove_lua_filter(app: AppHandle, id: String) -> Result<(), String> {
+    let mut user = load_user_filters(&app)?;
+    let before = user.len();
+    user.retain(|f| f.id != id);
+    if user.len() == before {
+        return Err(format!("No registered filter named '{}' (built-in filters cannot be removed)", id));
+    }
+    save_user_filters(&app, &user)
+}
@@ -0,0 +1,42 @@
+-- Admonitions: divs with the class note, tip, important, warning or caution (including GitHub
+-- "> [!NOTE]" alerts, which pandoc reads as such divs) get a bold title. HTML and EPUB keep a
+-- styled div; other formats get a block quote, which every writer supports.
+
+local titles = {
+  note = "Note",
+  tip = "Tip",
+  important = "Important",
+  warning = "Warning",
+  caution = "Caution",
+}
+
+local function kind_of(div)
+  for _, class in ipairs(div.classes) do
+    if titles[class] then
+      return class
+    end
+  end
+  return nil
+end
+
+function Div(div)
+  local kind = kind_of(div)
+  if not kind then
+    return nil
+  end
+
+  -- pandoc's alerts extension already adds a title div; reuse its text
+  local content = pandoc.List(div.content)
+  local title = pandoc.Inlines({ pandoc.Str(div.attributes.title or titles[kind]) })
+  if #content > 0 and content[1].t == "Div" and content[1].classes:includes("title") then
+    title = pandoc.utils.blocks_to_inlines(content:remove(1).content)
+  end
+
+  if FORMAT:match("html") or FORMAT:match("epub") then
+    content:insert(1, pandoc.Div(pandoc.Para(title), pandoc.Attr("", { "admonition-title" })))
+    return pandoc.Div(content, pandoc.Attr(div.identifier, { "admonition", kind }))
+  end
+
+  content:insert(1, pandoc.Para({ pandoc.Strong(title) }))
+  return pandoc.BlockQuote(content)
+end
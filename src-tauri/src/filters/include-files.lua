@@ -0,0 +1,52 @@
+-- Include files: a code block with the class "include" lists files, one per line, whose contents
+-- replace the block. Paths are looked up as given, then in each --resource-path folder.
+-- Optional attributes: format (reader, default markdown) and shift-heading-level-by.
+
+local function find_file(name)
+  local candidates = { name }
+  if not pandoc.path.is_absolute(name) then
+    for _, dir in ipairs(PANDOC_STATE.resource_path or {}) do
+      table.insert(candidates, pandoc.path.join({ dir, name }))
+    end
+  end
+  for _, candidate in ipairs(candidates) do
+    local file = io.open(candidate, "r")
+    if file then
+      local content = file:read("a")
+      file:close()
+      return content
+    end
+  end
+  error("include-files: cannot find " .. name)
+end
+
+local function shift_headings(blocks, shift)
+  if shift == 0 then
+    return blocks
+  end
+  return blocks:walk({
+    Header = function(header)
+      header.level = math.max(1, header.level + shift)
+      return header
+    end,
+  })
+end
+
+function CodeBlock(block)
+  if not block.classes:includes("include") then
+    return nil
+  end
+
+  local reader = block.attributes.format or "markdown"
+  local shift = tonumber(block.attributes["shift-heading-level-by"]) or 0
+  local blocks = pandoc.List()
+  for line in block.text:gmatch("[^\r\n]+") do
+    local name = line:match("^%s*(.-)%s*$")
+    -- Lines starting with // are comments
+    if name ~= "" and not name:match("^//") then
+      local doc = pandoc.read(find_file(name), reader)
+      blocks:extend(shift_headings(doc.blocks, shift))
+    end
+  end
+  return blocks
+end
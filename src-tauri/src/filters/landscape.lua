@@ -0,0 +1,35 @@
+-- Landscape sections: a div with the class "landscape" is typeset on rotated pages in PDF/LaTeX
+-- output (pdflscape). Other formats keep the content unchanged.
+
+local used = false
+
+function Div(div)
+  if not div.classes:includes("landscape") or not (FORMAT:match("latex") or FORMAT == "pdf") then
+    return nil
+  end
+  used = true
+  local blocks = pandoc.List({ pandoc.RawBlock("latex", "\\begin{landscape}") })
+  blocks:extend(div.content)
+  blocks:insert(pandoc.RawBlock("latex", "\\end{landscape}"))
+  return blocks
+end
+
+function Meta(meta)
+  if not used then
+    return nil
+  end
+  local includes = meta["header-includes"]
+  if includes == nil then
+    includes = pandoc.MetaList({})
+  elseif pandoc.utils.type(includes) ~= "List" then
+    includes = pandoc.MetaList({ includes })
+  end
+  includes:insert(pandoc.MetaBlocks({ pandoc.RawBlock("latex", "\\usepackage{pdflscape}") }))
+  meta["header-includes"] = includes
+  return meta
+end
+
+return {
+  { Div = Div },
+  { Meta = Meta },
+}
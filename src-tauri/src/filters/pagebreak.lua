@@ -0,0 +1,41 @@
+-- Page breaks: a paragraph holding only \newpage or \pagebreak, or an <!-- pagebreak --> comment,
+-- becomes a page break in PDF/LaTeX, Word, ODT, HTML and EPUB output.
+
+local breaks = {
+  latex = pandoc.RawBlock("latex", "\\newpage{}"),
+  beamer = pandoc.RawBlock("latex", "\\newpage{}"),
+  context = pandoc.RawBlock("context", "\\page"),
+  docx = pandoc.RawBlock("openxml", '<w:p><w:r><w:br w:type="page"/></w:r></w:p>'),
+  odt = pandoc.RawBlock("opendocument", '<text:p text:style-name="Pagebreak"/>'),
+  html = pandoc.RawBlock("html", '<div style="page-break-after: always; break-after: page;"></div>'),
+}
+
+local function page_break()
+  if FORMAT:match("latex") or FORMAT == "pdf" then
+    return breaks.latex
+  elseif FORMAT:match("html") or FORMAT:match("epub") then
+    return breaks.html
+  end
+  return breaks[FORMAT] or {}
+end
+
+local function is_break_command(text)
+  return text:match("^%s*\\newpage%s*$") or text:match("^%s*\\pagebreak%s*$")
+end
+
+function RawBlock(block)
+  if (block.format == "tex" or block.format == "latex") and is_break_command(block.text) then
+    return page_break()
+  end
+  if block.format == "html" and block.text:match("^%s*<!%-%-%s*pagebreak%s*%-%->%s*$") then
+    return page_break()
+  end
+  return nil
+end
+
+function Para(para)
+  if #para.content == 1 and para.content[1].t == "RawInline" and is_break_command(para.content[1].text) then
+    return page_break()
+  end
+  return nil
+end
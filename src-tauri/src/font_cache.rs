@@ -0,0 +1,204 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::fonts::{font_catalog, FontFamily};
+
+const CACHE_FILE: &str = "font-catalog.json";
+
+// Bump when FontFamily changes shape so caches written by older versions are rebuilt
+const CACHE_VERSION: u32 = 1;
+
+// Font packages install into nested directories (/usr/share/fonts/truetype/dejavu), and only the
+// directory that gained a file gets a new mtime, so look a few levels down
+const MAX_DIR_DEPTH: usize = 3;
+
+// Modification time of one font directory; missing directories are recorded as 0 so creating
+// ~/.fonts or ~/Library/Fonts later also invalidates the cache
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct DirStamp {
+    path: String,
+    modified: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FontCache {
+    version: u32,
+    stamps: Vec<DirStamp>,
+    catalog: Vec<FontFamily>,
+}
+
+// Payload of the `fonts-changed` event sent after a background refresh finds new or removed families
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FontsChanged {
+    added: Vec<String>,
+    removed: Vec<String>,
+    catalog: Vec<FontFamily>,
+}
+
+fn font_dirs() -> Vec<PathBuf> {
+    let home = dirs::home_dir();
+    let mut candidates: Vec<PathBuf> = Vec::new();
+
+    if cfg!(target_os = "macos") {
+        candidates.push("/System/Library/Fonts".into());
+        candidates.push("/Library/Fonts".into());
+        if let Some(home) = &home {
+            candidates.push(home.join("Library").join("Fonts"));
+        }
+    } else if cfg!(target_os = "windows") {
+        if let Ok(windir) = env::var("WINDIR") {
+            candidates.push(Path::new(&windir).join("Fonts"));
+        }
+        if let Ok(local) = env::var("LOCALAPPDATA") {
+            candidates.push(Path::new(&local).join("Microsoft").join("Windows").join("Fonts"));
+        }
+    } else {
+        candidates.push("/usr/share/fonts".into());
+        candidates.push("/usr/local/share/fonts".into());
+        if let Some(home) = &home {
+            candidates.push(home.join(".local").join("share").join("fonts"));
+            candidates.push(home.join(".fonts"));
+        }
+    }
+
+    candidates
+}
+
+fn modified_secs(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn collect_stamps(dir: &Path, depth: usize, stamps: &mut Vec<DirStamp>) {
+    stamps.push(DirStamp { path: dir.to_string_lossy().to_string(), modified: modified_secs(dir) });
+    if depth >= MAX_DIR_DEPTH {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect_stamps(&entry.path(), depth + 1, stamps);
+        }
+    }
+}
+
+fn current_stamps() -> Vec<DirStamp> {
+    let mut stamps = Vec::new();
+    for dir in font_dirs() {
+        collect_stamps(&dir, 0, &mut stamps);
+    }
+    stamps.sort_by(|a, b| a.path.cmp(&b.path));
+    stamps
+}
+
+fn cache_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(CACHE_FILE))
+        .map_err(|e| format!("Could not find app cache directory: {}", e))
+}
+
+fn load_cache(app: &AppHandle) -> Option<FontCache> {
+    let path = cache_path(app).ok()?;
+    let content = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str::<FontCache>(&content) {
+        Ok(cache) if cache.version == CACHE_VERSION => Some(cache),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Ignoring unreadable font cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn save_cache(app: &AppHandle, cache: &FontCache) -> Result<(), String> {
+    let path = cache_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string(cache)
+        .map_err(|e| format!("Failed to serialize font cache: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Scan fonts and write the cache; an empty catalog (no fc-list/PowerShell) isn't cached so
+// the next start tries again
+fn rebuild(app: &AppHandle, stamps: Vec<DirStamp>) -> Vec<FontFamily> {
+    let catalog = font_catalog();
+    if !catalog.is_empty() {
+        let cache = FontCache { version: CACHE_VERSION, stamps, catalog };
+        if let Err(e) = save_cache(app, &cache) {
+            warn!("Failed to save font cache: {}", e);
+        }
+        return cache.catalog;
+    }
+    catalog
+}
+
+// Compare font directory mtimes against the cache and rescan if anything changed
+fn refresh(app: &AppHandle, cached: FontCache) {
+    let stamps = current_stamps();
+    if stamps == cached.stamps {
+        return;
+    }
+
+    info!("Font directories changed, rescanning fonts");
+    let catalog = rebuild(app, stamps);
+    // An empty rescan means fc-list/PowerShell failed, not that every font was removed; the cached
+    // catalog stays and is checked again next start
+    if catalog.is_empty() {
+        warn!("Font rescan returned no fonts, keeping the cached catalog");
+        return;
+    }
+    let added: Vec<String> = catalog.iter()
+        .filter(|f| !cached.catalog.iter().any(|c| c.family == f.family))
+        .map(|f| f.family.clone())
+        .collect();
+    let removed: Vec<String> = cached.catalog.iter()
+        .filter(|c| !catalog.iter().any(|f| f.family == c.family))
+        .map(|c| c.family.clone())
+        .collect();
+
+    if !added.is_empty() || !removed.is_empty() {
+        info!("Fonts changed: {} added, {} removed", added.len(), removed.len());
+        let _ = app.emit("fonts-changed", FontsChanged { added, removed, catalog });
+    }
+}
+
+// Family names for building headers and checks: the cached catalog as-is, scanning only when there
+// is no cache yet. The cache is refreshed when the font picker lists the catalog
+pub fn cached_families(app: &AppHandle) -> Vec<String> {
+    let catalog = match load_cache(app) {
+        Some(cached) => cached.catalog,
+        None => rebuild(app, current_stamps()),
+    };
+    catalog.into_iter().map(|family| family.family).collect()
+}
+
+// Returns the cached catalog immediately when there is one and checks for font changes in the
+// background (emitting `fonts-changed`); the first run scans synchronously
+#[tauri::command]
+pub async fn list_font_catalog(app: AppHandle) -> Result<Vec<FontFamily>, String> {
+    if let Some(cached) = load_cache(&app) {
+        let catalog = cached.catalog.clone();
+        tokio::task::spawn_blocking(move || refresh(&app, cached));
+        return Ok(catalog);
+    }
+
+    tokio::task::spawn_blocking(move || rebuild(&app, current_stamps()))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))
+}
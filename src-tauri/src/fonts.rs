@@ -0,0 +1,829 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::font_cache::cached_families;
+use crate::scope;
+
+// A Unicode block the fallback header tries to cover, with fonts known to cover it
+// `xetex_class` is the ucharclasses class name used for XeLaTeX transitions
+struct GlyphBlock {
+    name: &'static str,
+    start: u32,
+    end: u32,
+    xetex_class: &'static str,
+    candidates: &'static [&'static str],
+}
+
+const MONO_SYMBOL_FONTS: &[&str] = &["DejaVu Sans Mono", "Menlo", "Noto Sans Mono", "Cascadia Mono", "Consolas", "Liberation Mono"];
+const SYMBOL_FONTS: &[&str] = &["DejaVu Sans", "Apple Symbols", "Noto Sans Symbols", "Noto Sans Symbols 2", "Segoe UI Symbol", "Symbola"];
+const MATH_FONTS: &[&str] = &["DejaVu Sans", "STIX Two Math", "Cambria Math", "Noto Sans Math", "Apple Symbols"];
+const EMOJI_FONTS: &[&str] = &["Noto Color Emoji", "Apple Color Emoji", "Segoe UI Emoji", "Noto Emoji", "Symbola"];
+const CJK_FONTS: &[&str] = &["Noto Sans CJK SC", "Noto Sans Mono CJK SC", "Source Han Sans SC", "PingFang SC", "Hiragino Sans GB", "Microsoft YaHei"];
+
+const GLYPH_BLOCKS: &[GlyphBlock] = &[
+    GlyphBlock { name: "Box Drawing", start: 0x2500, end: 0x257F, xetex_class: "BoxDrawing", candidates: MONO_SYMBOL_FONTS },
+    GlyphBlock { name: "Block Elements", start: 0x2580, end: 0x259F, xetex_class: "BlockElements", candidates: MONO_SYMBOL_FONTS },
+    GlyphBlock { name: "Arrows", start: 0x2190, end: 0x21FF, xetex_class: "Arrows", candidates: SYMBOL_FONTS },
+    GlyphBlock { name: "Mathematical Operators", start: 0x2200, end: 0x22FF, xetex_class: "MathematicalOperators", candidates: MATH_FONTS },
+    GlyphBlock { name: "Geometric Shapes", start: 0x25A0, end: 0x25FF, xetex_class: "GeometricShapes", candidates: SYMBOL_FONTS },
+    GlyphBlock { name: "Miscellaneous Symbols", start: 0x2600, end: 0x26FF, xetex_class: "MiscellaneousSymbols", candidates: SYMBOL_FONTS },
+    GlyphBlock { name: "Dingbats", start: 0x2700, end: 0x27BF, xetex_class: "Dingbats", candidates: SYMBOL_FONTS },
+    // Emoji live in several Unicode blocks; ucharclasses switches fonts per block, so each gets its own class
+    GlyphBlock { name: "Miscellaneous Symbols and Pictographs", start: 0x1F300, end: 0x1F5FF, xetex_class: "MiscellaneousSymbolsAndPictographs", candidates: EMOJI_FONTS },
+    GlyphBlock { name: "Emoticons", start: 0x1F600, end: 0x1F64F, xetex_class: "Emoticons", candidates: EMOJI_FONTS },
+    GlyphBlock { name: "Transport and Map Symbols", start: 0x1F680, end: 0x1F6FF, xetex_class: "TransportAndMapSymbols", candidates: EMOJI_FONTS },
+    GlyphBlock { name: "Supplemental Symbols and Pictographs", start: 0x1F900, end: 0x1F9FF, xetex_class: "SupplementalSymbolsAndPictographs", candidates: EMOJI_FONTS },
+    GlyphBlock { name: "CJK Unified Ideographs", start: 0x4E00, end: 0x9FFF, xetex_class: "CJKUnifiedIdeographs", candidates: CJK_FONTS },
+];
+
+// What the frontend tells us about the PDF build
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UnicodeHeaderOptions {
+    pub engine: String,
+    pub main_font: Option<String>,
+    pub mono_font: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GlyphRange {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UnicodeHeader {
+    // LaTeX to pass with -H
+    pub content: String,
+    // "lualatex", "xelatex" or "pdflatex" flavour that was generated
+    pub variant: String,
+    // Fallback fonts that are installed and used
+    pub fallback_fonts: Vec<String>,
+    // Blocks none of the installed fallback fonts cover
+    pub uncovered: Vec<GlyphRange>,
+}
+
+fn glyph_range(block: &GlyphBlock) -> GlyphRange {
+    GlyphRange {
+        name: block.name.to_string(),
+        start: format!("U+{:04X}", block.start),
+        end: format!("U+{:04X}", block.end),
+    }
+}
+
+// Escape a font name for use inside a fontspec argument
+fn latex_font_name(name: &str) -> String {
+    name.replace('\\', "").replace(['{', '}'], "")
+}
+
+// First installed candidate for a block, matching font families case-insensitively
+// XeTeX can't render color (bitmap) emoji fonts, so those are skipped when `allow_color` is false
+fn installed_candidate(block: &GlyphBlock, installed: &[String], allow_color: bool) -> Option<String> {
+    block.candidates.iter()
+        .filter(|candidate| allow_color || !candidate.contains("Color"))
+        .find_map(|candidate| installed.iter().find(|font| font.eq_ignore_ascii_case(candidate)).cloned())
+}
+
+// Build the Unicode fallback header for the chosen engine and fonts
+// LuaLaTeX gets luaotfload fallback chains, XeLaTeX/Tectonic get ucharclasses font transitions,
+// and pdfLaTeX (no system fonts) only gets pmboxdraw for box-drawing characters
+pub fn unicode_header(options: &UnicodeHeaderOptions, installed: &[String]) -> UnicodeHeader {
+    let mut fallback_fonts: Vec<String> = Vec::new();
+    let mut covered: Vec<(&GlyphBlock, String)> = Vec::new();
+    let mut uncovered = Vec::new();
+
+    let allow_color = options.engine == "lualatex";
+
+    for block in GLYPH_BLOCKS {
+        match installed_candidate(block, installed, allow_color) {
+            Some(font) => {
+                if !fallback_fonts.contains(&font) {
+                    fallback_fonts.push(font.clone());
+                }
+                covered.push((block, font));
+            },
+            None => uncovered.push(glyph_range(block)),
+        }
+    }
+
+    let main_font = options.main_font.as_deref().filter(|f| !f.is_empty());
+    let mono_font = options.mono_font.as_deref().filter(|f| !f.is_empty());
+
+    match options.engine.as_str() {
+        "lualatex" => {
+            let chain = fallback_fonts.iter()
+                .map(|font| format!("    \"{}:mode=harf;\",", latex_font_name(font)))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let mut content = format!(
+                "\\usepackage{{fontspec}}\n\\directlua{{\n  luaotfload.add_fallback(\"unicodefallback\", {{\n{}\n  }})\n}}\n",
+                chain
+            );
+            // Fonts loaded after this header (templates, \setmainfont in later headers) pick up the chain
+            content.push_str("\\defaultfontfeatures+{RawFeature={fallback=unicodefallback}}\n");
+            // The template has already loaded -V mainfont/monofont, so only re-declare the system fonts the
+            // user picked; project fonts (loaded from files with Path=) and template defaults stay as they are
+            for (command, font) in [("setmainfont", main_font), ("setmonofont", mono_font)] {
+                if let Some(font) = font.filter(|font| installed.iter().any(|family| family.eq_ignore_ascii_case(font))) {
+                    content.push_str(&format!(
+                        "\\{}{{{}}}[RawFeature={{fallback=unicodefallback}}]\n",
+                        command,
+                        latex_font_name(font)
+                    ));
+                }
+            }
+            UnicodeHeader { content, variant: "lualatex".to_string(), fallback_fonts, uncovered }
+        },
+        "xelatex" | "tectonic" => {
+            let classes = covered.iter().map(|(block, _)| block.xetex_class).collect::<Vec<_>>().join(",");
+            let mut content = format!("\\usepackage{{fontspec}}\n\\usepackage[{}]{{ucharclasses}}\n", classes);
+            for (index, font) in fallback_fonts.iter().enumerate() {
+                content.push_str(&format!(
+                    "\\newfontfamily\\unicodefallback{}{{{}}}\n",
+                    letter_suffix(index),
+                    latex_font_name(font)
+                ));
+            }
+            for (block, font) in &covered {
+                let index = fallback_fonts.iter().position(|f| f == font).unwrap_or_default();
+                content.push_str(&format!(
+                    "\\setTransitionsFor{{{}}}{{\\begingroup\\unicodefallback{}}}{{\\endgroup}}\n",
+                    block.xetex_class,
+                    letter_suffix(index)
+                ));
+            }
+            UnicodeHeader { content, variant: "xelatex".to_string(), fallback_fonts, uncovered }
+        },
+        _ => {
+            // pdfLaTeX can't load system fonts, so only box drawing can be handled (via pmboxdraw)
+            let uncovered = GLYPH_BLOCKS.iter()
+                .filter(|block| block.xetex_class != "BoxDrawing" && block.xetex_class != "BlockElements")
+                .map(glyph_range)
+                .collect();
+            UnicodeHeader {
+                content: "\\usepackage{pmboxdraw}\n".to_string(),
+                variant: "pdflatex".to_string(),
+                fallback_fonts: vec![],
+                uncovered,
+            }
+        },
+    }
+}
+
+// LaTeX command names can't contain digits, so number font families with letters (a, b, c...)
+fn letter_suffix(index: usize) -> String {
+    let mut n = index;
+    let mut out = String::new();
+    loop {
+        out.insert(0, (b'a' + (n % 26) as u8) as char);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    out
+}
+
+// Fonts LaTeX falls back to when none is selected
+const DEFAULT_MAIN_FONT: &str = "Latin Modern Roman";
+const DEFAULT_MONO_FONT: &str = "Latin Modern Mono";
+
+// Stop asking fontconfig for candidates after this many distinct missing characters
+const MAX_CANDIDATE_LOOKUPS: usize = 64;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingGlyph {
+    pub code_point: String,
+    pub character: String,
+    pub occurrences: usize,
+    // Installed families that do contain the character
+    pub candidates: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FontCoverage {
+    pub requested: String,
+    // Family fontconfig actually matched (differs from `requested` when the font isn't installed)
+    pub resolved: String,
+    pub checked: usize,
+    pub missing: Vec<MissingGlyph>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReport {
+    pub body: FontCoverage,
+    pub code: FontCoverage,
+}
+
+// Split Markdown into code (fenced blocks and inline spans) and body text
+pub fn split_code_and_body(text: &str) -> (String, String) {
+    let mut code = String::new();
+    let mut body = String::new();
+    let mut fence: Option<String> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(open) = &fence {
+            if trimmed.starts_with(open.as_str()) {
+                fence = None;
+            } else {
+                code.push_str(line);
+                code.push('\n');
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let marker: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+            fence = Some(marker);
+            continue;
+        }
+
+        // Inline code spans alternate with body text between backticks
+        for (index, part) in line.split('`').enumerate() {
+            if index % 2 == 1 {
+                code.push_str(part);
+            } else {
+                body.push_str(part);
+            }
+        }
+        body.push('\n');
+    }
+
+    (code, body)
+}
+
+// Count distinct printable characters (whitespace and controls can't be "missing")
+fn count_chars(text: &str) -> Vec<(char, usize)> {
+    let mut counts: std::collections::BTreeMap<char, usize> = std::collections::BTreeMap::new();
+    for c in text.chars().filter(|c| !c.is_whitespace() && !c.is_control()) {
+        *counts.entry(c).or_default() += 1;
+    }
+    counts.into_iter().collect()
+}
+
+// fontconfig patterns treat these as separators, so they're escaped inside family names
+fn fc_pattern_escape(family: &str) -> String {
+    family.chars().fold(String::new(), |mut out, c| {
+        if matches!(c, '\\' | '-' | ':' | ',') {
+            out.push('\\');
+        }
+        out.push(c);
+        out
+    })
+}
+
+// File of the family's upright face; fc-list matches the family exactly instead of substituting like fc-match
+fn font_file(font: &str) -> Result<String, String> {
+    let output = Command::new("fc-list")
+        .args(["-f", "%{style}|%{file}\n", fc_pattern_escape(font).as_str()])
+        .output()
+        .map_err(|e| format!("fontconfig (fc-list) is required to check font coverage: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("fc-list failed for {}: {}", font, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let faces: Vec<(String, String)> = text.lines()
+        .filter_map(|line| line.split_once('|'))
+        .map(|(style, file)| (style.to_lowercase(), file.to_string()))
+        .collect();
+
+    faces.iter()
+        .find(|(style, _)| ["regular", "book", "roman", "normal"].iter().any(|upright| style.split(',').any(|s| s.trim() == *upright)))
+        .or_else(|| faces.first())
+        .map(|(_, file)| file.clone())
+        .ok_or_else(|| format!("Font '{}' is not installed", font))
+}
+
+// Read the family's own charset ("20-7e a0-17f 2500 ...") from its font file with fc-query
+fn font_charset(font: &str) -> Result<(String, Vec<(u32, u32)>), String> {
+    let file = font_file(font)?;
+    let output = Command::new("fc-query")
+        .args(["-f", "%{family}|%{charset}\n", file.as_str()])
+        .output()
+        .map_err(|e| format!("fontconfig (fc-query) is required to check font coverage: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("fc-query failed for {}: {}", file, String::from_utf8_lossy(&output.stderr)));
+    }
+
+    // Collections (.ttc) list one line per face; use the face of the requested family
+    let text = String::from_utf8_lossy(&output.stdout);
+    let faces: Vec<(&str, &str)> = text.lines()
+        .map(|line| line.split_once('|').unwrap_or((line, "")))
+        .collect();
+    let (families, charset) = faces.iter()
+        .find(|(families, _)| families.split(',').any(|family| family.trim().eq_ignore_ascii_case(font)))
+        .or_else(|| faces.first())
+        .copied()
+        .unwrap_or_default();
+    let family = families.split(',').next().unwrap_or_default().trim().to_string();
+
+    Ok((family, parse_charset(charset)))
+}
+
+fn parse_charset(charset: &str) -> Vec<(u32, u32)> {
+    charset
+        .split_whitespace()
+        .filter_map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            Some((u32::from_str_radix(start, 16).ok()?, u32::from_str_radix(end, 16).ok()?))
+        })
+        .collect()
+}
+
+// Installed families whose charset contains the character
+fn fonts_covering(c: char) -> Vec<String> {
+    let Ok(output) = Command::new("fc-list")
+        .args([format!(":charset={:x}", c as u32).as_str(), "family"])
+        .output()
+    else {
+        return vec![];
+    };
+
+    let mut families: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .map(|family| family.trim().to_string())
+        .filter(|family| !family.is_empty() && !family.starts_with('.'))
+        .collect();
+    families.sort();
+    families.dedup();
+    families.truncate(5);
+    families
+}
+
+fn check_coverage(font: &str, text: &str, lookups: &mut usize) -> Result<FontCoverage, String> {
+    let (resolved, ranges) = font_charset(font)?;
+    let chars = count_chars(text);
+
+    let missing = chars.iter()
+        .filter(|(c, _)| {
+            let code = *c as u32;
+            !ranges.iter().any(|(start, end)| (*start..=*end).contains(&code))
+        })
+        .map(|(c, occurrences)| {
+            let candidates = if *lookups < MAX_CANDIDATE_LOOKUPS {
+                *lookups += 1;
+                fonts_covering(*c)
+            } else {
+                vec![]
+            };
+            MissingGlyph {
+                code_point: format!("U+{:04X}", *c as u32),
+                character: c.to_string(),
+                occurrences: *occurrences,
+                candidates,
+            }
+        })
+        .collect();
+
+    Ok(FontCoverage { requested: font.to_string(), resolved, checked: chars.len(), missing })
+}
+
+// Check the characters used in an input document against the selected body and code fonts
+#[tauri::command]
+pub async fn scan_font_coverage(path: String, main_font: Option<String>, mono_font: Option<String>) -> Result<CoverageReport, String> {
+    let path = scope::check_scoped(&path)?;
+    tokio::task::spawn_blocking(move || {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {} as text: {}", path.display(), e))?;
+        let (code, body) = split_code_and_body(&text);
+
+        let main_font = main_font.filter(|f| !f.is_empty()).unwrap_or_else(|| DEFAULT_MAIN_FONT.to_string());
+        let mono_font = mono_font.filter(|f| !f.is_empty()).unwrap_or_else(|| DEFAULT_MONO_FONT.to_string());
+
+        let mut lookups = 0;
+        Ok(CoverageReport {
+            body: check_coverage(&main_font, &body, &mut lookups)?,
+            code: check_coverage(&mono_font, &code, &mut lookups)?,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+// Preview the header for the current settings so the UI can report uncovered ranges
+#[tauri::command]
+pub fn preview_unicode_header(app: AppHandle, options: UnicodeHeaderOptions) -> UnicodeHeader {
+    unicode_header(&options, &cached_families(&app))
+}
+
+// fontconfig spacing value for monospace/charcell fonts (dual-width counts too, e.g. CJK mono)
+const FC_SPACING_MONO: u32 = 100;
+const FC_SPACING_DUAL: u32 = 90;
+const FC_SPACING_CHARCELL: u32 = 110;
+// fontconfig weights at or above this are bold; slants above 0 are italic/oblique
+const FC_WEIGHT_BOLD: u32 = 180;
+
+// Scripts summarized from fontconfig language coverage, keyed by a representative language
+const SCRIPT_LANGS: &[(&str, &str)] = &[
+    ("Latin", "en"), ("Greek", "el"), ("Cyrillic", "ru"), ("Hebrew", "he"), ("Arabic", "ar"),
+    ("Devanagari", "hi"), ("Thai", "th"), ("Chinese", "zh-cn"), ("Japanese", "ja"), ("Korean", "ko"),
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFamily {
+    pub family: String,
+    // Style names as reported by the font (Regular, Bold, Condensed Italic...)
+    pub styles: Vec<String>,
+    pub has_regular: bool,
+    pub has_bold: bool,
+    pub has_italic: bool,
+    pub has_bold_italic: bool,
+    pub monospace: bool,
+    pub files: Vec<String>,
+    pub scripts: Vec<String>,
+    // Regular/bold/italic/bold italic faces fontspec would have to fake (empty unless detailed)
+    pub missing_styles: Vec<String>,
+    // false when only family names were available (no fontconfig), so the flags above are guesses
+    pub detailed: bool,
+}
+
+impl FontFamily {
+    fn new(family: &str) -> Self {
+        FontFamily {
+            family: family.to_string(),
+            styles: vec![],
+            has_regular: false,
+            has_bold: false,
+            has_italic: false,
+            has_bold_italic: false,
+            monospace: false,
+            files: vec![],
+            scripts: vec![],
+            missing_styles: vec![],
+            detailed: false,
+        }
+    }
+
+    fn compute_missing_styles(&self) -> Vec<String> {
+        [
+            (self.has_regular, "regular"),
+            (self.has_bold, "bold"),
+            (self.has_italic, "italic"),
+            (self.has_bold_italic, "bold italic"),
+        ]
+        .iter()
+        .filter(|(present, _)| !present)
+        .map(|(_, name)| name.to_string())
+        .collect()
+    }
+}
+
+// fontconfig format for one face per line: family, style, spacing, weight, slant, file, langs (tab separated)
+const FC_FACE_FORMAT: &str = "%{family[0]}\t%{style[0]}\t%{spacing}\t%{weight}\t%{slant}\t%{file}\t%{lang}\n";
+
+// One font file (face) as reported by fc-list/fc-scan
+struct FcFace {
+    family: String,
+    style: String,
+    spacing: Option<u32>,
+    weight: u32,
+    slant: u32,
+    file: String,
+    langs: Vec<String>,
+}
+
+impl FcFace {
+    fn is_bold(&self) -> bool {
+        self.weight >= FC_WEIGHT_BOLD
+    }
+
+    fn is_italic(&self) -> bool {
+        self.slant > 0
+    }
+}
+
+fn parse_fc_faces(text: &str) -> Vec<FcFace> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() < 7 {
+                return None;
+            }
+            let family = fields[0].trim();
+            if family.is_empty() || family.starts_with('.') {
+                return None;
+            }
+            Some(FcFace {
+                family: family.to_string(),
+                style: fields[1].trim().to_string(),
+                spacing: fields[2].trim().parse().ok(),
+                weight: fields[3].trim().parse().unwrap_or(80),
+                slant: fields[4].trim().parse().unwrap_or(0),
+                file: fields[5].trim().to_string(),
+                langs: fields[6].split('|').map(|lang| lang.to_string()).collect(),
+            })
+        })
+        .collect()
+}
+
+// Group faces into families with their styles, files and script coverage
+fn group_faces(faces: Vec<FcFace>) -> Vec<FontFamily> {
+    let mut families: Vec<FontFamily> = Vec::new();
+
+    for face in faces {
+        let index = match families.iter().position(|f| f.family == face.family) {
+            Some(index) => index,
+            None => {
+                families.push(FontFamily::new(&face.family));
+                families.len() - 1
+            },
+        };
+        let family = &mut families[index];
+        family.detailed = true;
+
+        if !face.style.is_empty() && !family.styles.contains(&face.style) {
+            family.styles.push(face.style.clone());
+        }
+        if !face.file.is_empty() && !family.files.contains(&face.file) {
+            family.files.push(face.file.clone());
+        }
+
+        match (face.is_bold(), face.is_italic()) {
+            (false, false) => family.has_regular = true,
+            (true, false) => family.has_bold = true,
+            (false, true) => family.has_italic = true,
+            (true, true) => family.has_bold_italic = true,
+        }
+
+        if matches!(face.spacing, Some(FC_SPACING_MONO) | Some(FC_SPACING_DUAL) | Some(FC_SPACING_CHARCELL)) {
+            family.monospace = true;
+        }
+
+        for (script, lang) in SCRIPT_LANGS {
+            if face.langs.iter().any(|l| l == lang) && !family.scripts.iter().any(|s| s == script) {
+                family.scripts.push(script.to_string());
+            }
+        }
+    }
+
+    for family in &mut families {
+        family.missing_styles = family.compute_missing_styles();
+    }
+    families.sort_by_key(|f| f.family.to_lowercase());
+    families
+}
+
+// Rich font catalog: styles, monospace flag, files and script coverage per family
+// Uses fontconfig where available; otherwise falls back to plain family names
+pub fn font_catalog() -> Vec<FontFamily> {
+    let output = Command::new("fc-list")
+        .args(["--format", FC_FACE_FORMAT])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => group_faces(parse_fc_faces(&String::from_utf8_lossy(&output.stdout))),
+        _ => list_system_fonts()
+            .unwrap_or_default()
+            .iter()
+            .map(|family| FontFamily::new(family))
+            .collect(),
+    }
+}
+
+// Project fonts: brand fonts kept in a `fonts/` folder next to the document instead of installed
+// system-wide. They are loaded by file name (fontspec `Path=`) and exposed to kpathsea via OSFONTDIR
+pub const PROJECT_FONTS_DIR: &str = "fonts";
+const FONT_FILE_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc"];
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFonts {
+    // Resolved fonts directory, None when the input has no fonts folder
+    pub dir: Option<String>,
+    pub families: Vec<FontFamily>,
+}
+
+// Project font families selected for a conversion
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectFontOptions {
+    pub dir: String,
+    pub main_font: Option<String>,
+    pub mono_font: Option<String>,
+}
+
+// The configured folder (relative to the input's directory, or absolute), else `fonts/` next to the input
+pub fn resolve_project_fonts_dir(input: &Path, configured: Option<&str>) -> Option<PathBuf> {
+    let base = input.parent()?;
+    let dir = match configured.map(str::trim).filter(|dir| !dir.is_empty()) {
+        Some(dir) => base.join(dir),
+        None => base.join(PROJECT_FONTS_DIR),
+    };
+    dir.is_dir().then_some(dir)
+}
+
+fn collect_font_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_font_files(&path, files);
+        } else if path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| FONT_FILE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        {
+            files.push(path);
+        }
+    }
+}
+
+// Faces in a fonts directory via fc-scan; without fontconfig (macOS/Windows without it installed),
+// family and style are guessed from file names like BrandSans-BoldItalic.otf
+fn scan_font_dir(dir: &Path) -> (Vec<FcFace>, bool) {
+    let output = Command::new("fc-scan")
+        .args(["--format", FC_FACE_FORMAT])
+        .arg(dir)
+        .output();
+    if let Ok(output) = output {
+        if output.status.success() {
+            return (parse_fc_faces(&String::from_utf8_lossy(&output.stdout)), true);
+        }
+    }
+
+    let mut files = Vec::new();
+    collect_font_files(dir, &mut files);
+    let faces = files
+        .iter()
+        .filter_map(|file| {
+            let stem = file.file_stem()?.to_string_lossy().to_string();
+            let (family, style) = stem.rsplit_once('-').unwrap_or((stem.as_str(), "Regular"));
+            let lower = style.to_lowercase();
+            Some(FcFace {
+                family: family.to_string(),
+                style: style.to_string(),
+                spacing: None,
+                weight: if lower.contains("bold") { 200 } else { 80 },
+                slant: if lower.contains("italic") || lower.contains("oblique") { 100 } else { 0 },
+                file: file.to_string_lossy().to_string(),
+                langs: vec![],
+            })
+        })
+        .collect();
+    (faces, false)
+}
+
+pub fn project_font_families(dir: &Path) -> Vec<FontFamily> {
+    let (faces, detailed) = scan_font_dir(dir);
+    let mut families = group_faces(faces);
+    if !detailed {
+        // Styles guessed from file names aren't reliable enough to warn about
+        for family in &mut families {
+            family.detailed = false;
+            family.missing_styles.clear();
+        }
+    }
+    families
+}
+
+// fontspec loads by file name relative to Path=, with forward slashes even on Windows
+fn relative_font_file(dir: &Path, file: &str) -> String {
+    let path = Path::new(file);
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// Pick the face closest to regular/bold weight for the requested style; condensed and other
+// width variants have longer style names, so the shortest name wins ties
+fn pick_face<'a>(faces: &[&'a FcFace], bold: bool, italic: bool) -> Option<&'a FcFace> {
+    let target = if bold { 200 } else { 80 };
+    faces.iter()
+        .filter(|face| face.is_bold() == bold && face.is_italic() == italic)
+        .min_by_key(|face| (face.weight.abs_diff(target), face.style.len()))
+        .copied()
+}
+
+// Pandoc variables that load a project family from its files:
+// <prefix>font=<upright file> plus <prefix>fontoptions Path=/BoldFont=/ItalicFont=/BoldItalicFont=
+fn fontspec_file_variables(dir: &Path, faces: &[FcFace], family: &str, prefix: &str) -> Result<Vec<(String, String)>, String> {
+    let family_faces: Vec<&FcFace> = faces.iter().filter(|face| face.family == family).collect();
+    let upright = pick_face(&family_faces, false, false)
+        .or_else(|| family_faces.first().copied())
+        .ok_or_else(|| format!("Font '{}' not found in {}", family, dir.display()))?;
+
+    let option_key = format!("{}fontoptions", prefix);
+    let mut variables = vec![
+        (format!("{}font", prefix), relative_font_file(dir, &upright.file)),
+        (option_key.clone(), format!("Path={}/", dir.to_string_lossy().replace('\\', "/").trim_end_matches('/'))),
+    ];
+    for (option, bold, italic) in [("BoldFont", true, false), ("ItalicFont", false, true), ("BoldItalicFont", true, true)] {
+        if let Some(face) = pick_face(&family_faces, bold, italic) {
+            variables.push((option_key.clone(), format!("{}={}", option, relative_font_file(dir, &face.file))));
+        }
+    }
+    Ok(variables)
+}
+
+// Variables for the selected project main/mono fonts; fonts not in the project folder are left to
+// the -V mainfont/monofont the frontend already passed
+pub fn project_font_variables(options: &ProjectFontOptions) -> Result<Vec<(String, String)>, String> {
+    let dir = Path::new(&options.dir);
+    let (faces, _) = scan_font_dir(dir);
+    let mut variables = Vec::new();
+    if let Some(main) = &options.main_font {
+        variables.extend(fontspec_file_variables(dir, &faces, main, "main")?);
+    }
+    if let Some(mono) = &options.mono_font {
+        variables.extend(fontspec_file_variables(dir, &faces, mono, "mono")?);
+    }
+    Ok(variables)
+}
+
+#[tauri::command]
+pub async fn list_project_fonts(input_path: String, fonts_dir: Option<String>) -> Result<ProjectFonts, String> {
+    tokio::task::spawn_blocking(move || {
+        match resolve_project_fonts_dir(Path::new(&input_path), fonts_dir.as_deref()) {
+            Some(dir) => ProjectFonts {
+                families: project_font_families(&dir),
+                dir: Some(dir.to_string_lossy().to_string()),
+            },
+            None => ProjectFonts { dir: None, families: vec![] },
+        }
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))
+}
+
+#[tauri::command]
+pub fn list_system_fonts() -> Result<Vec<String>, String> {
+    use std::collections::HashSet;
+
+    // Platform-specific font listing
+    let output = if cfg!(target_os = "macos") {
+        // macOS: Try fc-list first, fall back to atsutil
+        Command::new("fc-list")
+            .args([":", "family"])
+            .output()
+            .or_else(|_| {
+                // Fallback: use atsutil on macOS (always available)
+                Command::new("sh")
+                    .args(["-c", "atsutil fonts -list | grep -v '^$' | sort -u"])
+                    .output()
+            })
+    } else if cfg!(target_os = "linux") {
+        // Linux: fc-list is standard on most distros
+        Command::new("fc-list")
+            .args([":", "family"])
+            .output()
+    } else if cfg!(target_os = "windows") {
+        // Windows: Use PowerShell with proper assembly loading
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "Add-Type -AssemblyName System.Drawing; (New-Object System.Drawing.Text.InstalledFontCollection).Families | ForEach-Object { $_.Name }"
+            ])
+            .output()
+    } else {
+        return Err("Unsupported platform".to_string());
+    };
+
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                let text = String::from_utf8_lossy(&output.stdout);
+                let mut fonts: HashSet<String> = HashSet::new();
+
+                for line in text.lines() {
+                    // fc-list may have multiple families separated by commas
+                    for part in line.split(',') {
+                        let font = part.trim().to_string();
+                        // Filter out empty lines, hidden fonts (starting with .), and system prefixes
+                        if !font.is_empty()
+                            && !font.starts_with('.')
+                            && !font.starts_with('#')
+                            && font.len() > 1
+                        {
+                            fonts.insert(font);
+                        }
+                    }
+                }
+
+                let mut result: Vec<String> = fonts.into_iter().collect();
+                result.sort_by_key(|a| a.to_lowercase());
+                Ok(result)
+            } else {
+                // Return empty list instead of error - font selection is optional
+                Ok(vec![])
+            }
+        }
+        Err(_) => {
+            // Return empty list if command fails
+            Ok(vec![])
+        }
+    }
+}
+
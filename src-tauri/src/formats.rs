@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use serde::Serialize;
+
+use crate::scope;
+
+// Pandoc readers by file extension; .txt and files without an extension are sniffed instead
+const EXTENSION_FORMATS: &[(&[&str], &str)] = &[
+    (&["md", "markdown", "mdown", "mkd", "mkdn", "mdwn", "mdtxt", "mdtext", "rmd", "qmd"], "markdown"),
+    (&["rst", "rest"], "rst"),
+    (&["tex", "latex", "ltx"], "latex"),
+    (&["html", "htm", "xhtml"], "html"),
+    (&["org"], "org"),
+    (&["docx"], "docx"),
+    (&["odt"], "odt"),
+    (&["epub"], "epub"),
+    (&["rtf"], "rtf"),
+    (&["ipynb"], "ipynb"),
+    (&["textile"], "textile"),
+    (&["typ"], "typst"),
+    (&["json"], "json"),
+    (&["csv"], "csv"),
+    (&["tsv"], "tsv"),
+    (&["opml"], "opml"),
+    (&["fb2"], "fb2"),
+    (&["muse"], "muse"),
+    (&["t2t"], "t2t"),
+    (&["wiki", "mediawiki"], "mediawiki"),
+    (&["dokuwiki"], "dokuwiki"),
+    (&["bib", "bibtex"], "bibtex"),
+];
+
+// Pandoc's Markdown reader family, which all take extensions such as +hard_line_breaks
+const MARKDOWN_READERS: &[&str] = &[
+    "markdown", "markdown_strict", "markdown_phpextra", "markdown_mmd", "markdown_github",
+    "commonmark", "commonmark_x", "gfm",
+];
+
+// Only the start of the file is sniffed
+const SNIFF_BYTES: u64 = 64 * 1024;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InputFormat {
+    // Pandoc reader name; None when nothing matched and pandoc should guess
+    pub format: Option<String>,
+    // What decided it: extension, front-matter, content or fallback
+    pub source: String,
+    // Readers that take Markdown extensions (+hard_line_breaks, -smart...); which ones each accepts
+    // is checked in the frontend
+    pub markdown: bool,
+}
+
+impl InputFormat {
+    fn new(format: &str, source: &str) -> Self {
+        InputFormat {
+            format: Some(format.to_string()),
+            source: source.to_string(),
+            markdown: MARKDOWN_READERS.contains(&format),
+        }
+    }
+}
+
+fn format_for_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    EXTENSION_FORMATS.iter()
+        .find(|(extensions, _)| extensions.contains(&extension.as_str()))
+        .map(|(_, format)| *format)
+}
+
+// A YAML metadata block opening the file: "---" on the first line, closed by "---" or "..."
+fn has_front_matter(text: &str) -> bool {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim_end) != Some("---") {
+        return false;
+    }
+    lines.take(200)
+        .map(str::trim_end)
+        .any(|line| line == "---" || line == "...")
+}
+
+// Guess the reader from the text itself, for .txt files and files without an extension
+fn sniff_format(text: &str) -> Option<&'static str> {
+    let text = text.trim_start_matches('\u{feff}');
+    let start = text.trim_start().to_lowercase();
+
+    if start.starts_with("<!doctype html") || start.starts_with("<html") || (start.starts_with("<?xml") && start.contains("<html")) {
+        return Some("html");
+    }
+    let lines: Vec<&str> = text.lines().take(500).collect();
+    let org_keyword = |line: &&str| {
+        let line = line.trim_start().to_lowercase();
+        ["#+title:", "#+author:", "#+begin_src", "#+options:", "#+startup:"].iter().any(|k| line.starts_with(k))
+    };
+    if lines.iter().any(org_keyword) {
+        return Some("org");
+    }
+
+    // reStructuredText directives are unambiguous; title underlines are checked after Markdown,
+    // whose setext headings look the same
+    let rst_directive = lines.iter().any(|line| {
+        let line = line.trim_start();
+        line.starts_with(".. ") && line.contains("::")
+    });
+    if rst_directive {
+        return Some("rst");
+    }
+
+    let markdown = lines.iter().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("# ") || line.starts_with("## ") || line.starts_with("```") || line.starts_with("> ")
+            || (line.contains("](") && line.contains('['))
+    });
+    if markdown {
+        return Some("markdown");
+    }
+
+    // LaTeX markers only count at the start of a line outside fenced code, so a Markdown file
+    // showing a LaTeX snippet isn't read as LaTeX
+    let mut fenced = false;
+    let latex = lines.iter().any(|line| {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            fenced = !fenced;
+            return false;
+        }
+        !fenced && (line.starts_with("\\documentclass") || line.starts_with("\\begin{document}"))
+    });
+    if latex {
+        return Some("latex");
+    }
+
+    let rst_underline = lines.windows(2).any(|pair| {
+        let (title, underline) = (pair[0].trim_end(), pair[1].trim_end());
+        !title.is_empty() && underline.len() >= title.chars().count() && underline.len() >= 3
+            && ['=', '~', '^', '"'].iter().any(|&c| underline.chars().all(|u| u == c))
+    });
+    rst_underline.then_some("rst")
+}
+
+fn detect(path: &Path) -> Result<InputFormat, String> {
+    if let Some(format) = format_for_extension(path) {
+        return Ok(InputFormat::new(format, "extension"));
+    }
+
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(SNIFF_BYTES).read_to_end(&mut bytes))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if bytes.contains(&0) {
+        return Ok(InputFormat { format: None, source: "fallback".to_string(), markdown: false });
+    }
+    let text = String::from_utf8_lossy(&bytes);
+
+    if has_front_matter(&text) {
+        return Ok(InputFormat::new("markdown", "front-matter"));
+    }
+    if let Some(format) = sniff_format(&text) {
+        return Ok(InputFormat::new(format, "content"));
+    }
+    // Plain prose reads fine as Markdown, which is also what pandoc assumes for unknown extensions
+    Ok(InputFormat::new("markdown", "fallback"))
+}
+
+// Reader for the input, so the command can pass -f instead of leaving pandoc to guess
+#[tauri::command]
+pub async fn detect_input_format(path: String) -> Result<InputFormat, String> {
+    let path = scope::check_scoped(&path)?;
+    tokio::task::spawn_blocking(move || detect(&path))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::JobWorkspace;
+
+    #[test]
+    fn front_matter_needs_a_closing_line() {
+        assert!(has_front_matter("---\ntitle: Notes\n...\nBody"));
+        assert!(has_front_matter("---\r\ntitle: Notes\r\n---\r\n"));
+        assert!(!has_front_matter("---\ntitle: Notes\n"));
+        assert!(!has_front_matter("Intro\n---\n"));
+    }
+
+    #[test]
+    fn sniffs_text_content() {
+        assert_eq!(sniff_format("\u{feff}<!DOCTYPE html>\n<html></html>"), Some("html"));
+        assert_eq!(sniff_format("\\documentclass{article}\n\\begin{document}"), Some("latex"));
+        assert_eq!(sniff_format("#+TITLE: Notes\n* Heading"), Some("org"));
+        assert_eq!(sniff_format("Title\n=====\n\n.. note:: Careful"), Some("rst"));
+        assert_eq!(sniff_format("# Title\n\nSee [the docs](https://example.org)."), Some("markdown"));
+        assert_eq!(sniff_format("Title\n=====\n\nPlain text."), Some("rst"));
+        assert_eq!(sniff_format("Just a few words of prose."), None);
+    }
+
+    #[test]
+    fn latex_in_markdown_code_stays_markdown() {
+        assert_eq!(sniff_format("Setup\n\n```latex\n\\documentclass{article}\n\\begin{document}\n```\n"), Some("markdown"));
+        assert_eq!(sniff_format("Setup\n\n~~~\n\\documentclass{article}\n~~~\n"), None);
+        assert_eq!(sniff_format("Use \\documentclass{article} to start.\n\n    \\begin{document}\n"), None);
+        assert_eq!(sniff_format("% notes\n\\documentclass{article}\n\\usepackage{amsmath}\n\\begin{document}\nHi\n\\end{document}\n"), Some("latex"));
+    }
+
+    #[test]
+    fn markdown_family_takes_extensions() {
+        assert!(InputFormat::new("gfm", "extension").markdown);
+        assert!(InputFormat::new("commonmark_x", "extension").markdown);
+        assert!(!InputFormat::new("rst", "extension").markdown);
+    }
+
+    #[test]
+    fn detects_txt_and_extensionless_files() {
+        let workspace = JobWorkspace::create().unwrap();
+        let detected = |name: &str, content: &[u8]| {
+            let path = workspace.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            let format = detect(&path).unwrap();
+            (format.format, format.source)
+        };
+
+        assert_eq!(detected("notes.md", b"<html>"), (Some("markdown".to_string()), "extension".to_string()));
+        assert_eq!(detected("notes.txt", b"---\ntitle: Notes\n---\nBody"), (Some("markdown".to_string()), "front-matter".to_string()));
+        assert_eq!(detected("README", b"Intro\n\n.. code-block:: rust\n"), (Some("rst".to_string()), "content".to_string()));
+        assert_eq!(detected("LICENSE", b"Plain prose."), (Some("markdown".to_string()), "fallback".to_string()));
+        assert_eq!(detected("blob", b"\x00\x01binary"), (None, "fallback".to_string()));
+    }
+}
@@ -2,16 +2,42 @@ use std::process::{Command, Stdio};
 use std::env;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{BufRead, BufReader};
 use std::fs;
 use std::path::Path;
+use std::time::SystemTime;
+use serde::Deserialize;
 use tauri::{AppHandle, Emitter};
 use tauri::menu::{Menu, MenuItem, Submenu, PredefinedMenuItem};
-use log::{info, error};
+use log::{info, warn, error};
+
+mod citations;
+mod community;
+mod deps;
+mod diagrams;
+mod doctor;
+mod filters;
+mod font_cache;
+mod fonts;
+mod formats;
+mod mermaid;
+mod projects;
+mod resources;
+mod scope;
+mod templates;
+mod themes;
+mod workspace;
+
+use citations::CitationOptions;
+use fonts::{ProjectFontOptions, UnicodeHeaderOptions};
+use mermaid::MermaidOptions;
+use workspace::{JobWorkspace, WorkingDir};
 
 // Track running install processes for cancellation
 static NEXT_INSTALL_ID: AtomicU32 = AtomicU32::new(1);
+// Bumped on every cancel, so multi-step operations notice a cancel that lands between their commands
+static CANCEL_REQUESTS: AtomicU32 = AtomicU32::new(0);
 lazy_static::lazy_static! {
     static ref RUNNING_INSTALLS: Mutex<HashMap<u32, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
 }
@@ -68,104 +94,230 @@ fn get_extended_path() -> String {
     }
 }
 
+// Lowercase letters, digits and dashes from a display or file name, so ids are safe as file names
+fn slug(name: &str, fallback: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let slug = slug.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-");
+    if slug.is_empty() { fallback.to_string() } else { slug }
+}
+
+// `base`, or `base-2`, `base-3`... for the first one not already taken
+fn unique_id(base: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut id = base.to_string();
+    let mut suffix = 2;
+    while taken(&id) {
+        id = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    id
+}
+
+// Per-conversion options: headers generated into the job workspace, paths, working directory and environment
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JobOptions {
+    // Output theme whose LaTeX header is passed with -H
+    theme_id: Option<String>,
+    // Engine and fonts to build the Unicode fallback font header for
+    unicode_header: Option<UnicodeHeaderOptions>,
+    // Main/mono fonts loaded from a project fonts folder instead of system fonts
+    project_fonts: Option<ProjectFontOptions>,
+    // Output file the command writes; added to the path scope so it can be opened afterwards
+    output_path: Option<String>,
+    input_path: Option<String>,
+    #[serde(default)]
+    working_dir: WorkingDir,
+    // Per-preset environment for pandoc, filters and the PDF engine
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    // Extra folders (relative to the input) added to --resource-path
+    #[serde(default)]
+    asset_dirs: Vec<String>,
+    // Pandoc writer (pdf, html, docx...); taken from -t in the command when missing
+    output_format: Option<String>,
+    #[serde(default)]
+    mermaid: MermaidOptions,
+    // Diagram languages found in the input (plantuml, graphviz, d2), rendered by the bundled Lua filter
+    #[serde(default)]
+    diagrams: Vec<String>,
+    // Enabled Lua filters (built-in or registered ids) in the order pandoc should run them
+    #[serde(default)]
+    lua_filters: Vec<String>,
+    // Bibliography files, CSL style and locale for citeproc
+    citations: Option<CitationOptions>,
+    // Library template or reference document for the output format
+    template_id: Option<String>,
+    // Chapters of a book project, in the order they appear in the command; input_path is the first
+    #[serde(default)]
+    chapters: Vec<String>,
+}
+
 #[tauri::command]
-fn check_command(command: String) -> Result<String, String> {
+fn run_pandoc(app: AppHandle, command: String, options: Option<JobOptions>) -> Result<String, String> {
+    info!("Running pandoc command: {}", command);
+    let options = options.unwrap_or_default();
     let extended_path = get_extended_path();
 
-    let output = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .args(["/C", &command])
-            .output()
-    } else {
-        Command::new("sh")
-            .args(["-c", &command])
-            .env("PATH", &extended_path)
-            .output()
-    };
+    // Everything this conversion generates goes into its own workspace, removed when it drops
+    let workspace = JobWorkspace::create()?;
+    // The webview's command is parsed and checked here, before the backend adds its own filters,
+    // headers and paths straight to the argument list; pandoc never runs through a shell
+    let mut args = workspace::pandoc_args(&command)?;
+    let target = options.output_format.clone()
+        .or_else(|| mermaid::target_format(&args))
+        .unwrap_or_else(|| "html".to_string());
+
+    // A --template or --reference-doc in the extra arguments wins over the preset's library template
+    if let Some(template_id) = &options.template_id {
+        let (flag, path) = templates::resolve(&app, template_id, &target)?;
+        if !workspace::has_option(&args, flag) {
+            args.push(format!("{}={}", flag, path.to_string_lossy()));
+        }
+    }
 
-    match output {
-        Ok(output) => {
-            if output.status.success() {
-                Ok(String::from_utf8_lossy(&output.stdout).to_string())
-            } else {
-                Err(format!("Command failed: {}", String::from_utf8_lossy(&output.stderr)))
+    if let Some(theme_id) = &options.theme_id {
+        let theme = themes::find_theme(&app, theme_id)?;
+        let header = workspace.write("theme-header.tex", &theme.to_latex())?;
+        args.extend(["-H".to_string(), header.to_string_lossy().to_string()]);
+    }
+
+    if let Some(unicode) = &options.unicode_header {
+        let header = fonts::unicode_header(unicode, &font_cache::cached_families(&app));
+        if !header.uncovered.is_empty() {
+            info!("Unicode fallback header leaves uncovered: {:?}", header.uncovered.iter().map(|r| &r.name).collect::<Vec<_>>());
+        }
+        let path = workspace.write("unicode-fix.tex", &header.content)?;
+        args.extend(["-H".to_string(), path.to_string_lossy().to_string()]);
+    }
+
+    if let Some(project) = &options.project_fonts {
+        for (name, value) in fonts::project_font_variables(project)? {
+            args.extend(["-V".to_string(), format!("{}={}", name, value)]);
+        }
+    }
+
+    if let Some(citations) = &options.citations {
+        let input = options.input_path.as_deref();
+        let bibliographies = citations::load_bibliographies(citations, input)?;
+
+        // pandoc renders unknown keys as "(key?)"; the scan is a text heuristic that can mistake
+        // code or raw blocks for citations, so it's logged like pandoc's own warning rather than fatal.
+        // The frontend asks before converting with undefined keys
+        if !bibliographies.is_empty() {
+            let inputs: Vec<&str> = match input {
+                _ if !options.chapters.is_empty() => options.chapters.iter().map(String::as_str).collect(),
+                Some(input) => vec![input],
+                None => vec![],
+            };
+            if inputs.iter().any(|p| citations::is_markdown(p)) {
+                let text = citations::read_markdown(&inputs)?;
+                let undefined = citations::undefined_keys(&citations::cited_keys(&text), &bibliographies);
+                if !undefined.is_empty() {
+                    warn!("Citation keys not found in the bibliography: {}", undefined.join(", "));
+                }
             }
         }
-        Err(e) => Err(format!("Failed to execute: {}", e)),
+
+        args.extend(citations::pandoc_args(citations, &bibliographies, input, &workspace)?);
+        if !bibliographies.is_empty() && !args.iter().any(|arg| arg == "--citeproc" || arg == "-C") {
+            args.push("--citeproc".to_string());
+        }
     }
-}
 
-#[tauri::command]
-fn run_pandoc(command: String) -> Result<String, String> {
-    info!("Running pandoc command: {}", command);
-    let extended_path = get_extended_path();
-    let home = env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-    let temp_dir = env::temp_dir();
-
-    // Try to copy mermaid config file to home directory if it exists in the bundle
-    // This ensures mermaid-filter uses proper configuration for SVG rendering with text
-    let app_dir = env::current_exe()
-        .ok()
-        .and_then(|exe| exe.parent().map(|p| p.to_path_buf()));
-
-    if let Some(app_dir) = app_dir {
-        // Try multiple possible locations for the config file
-        let possible_paths = vec![
-            app_dir.join(".mermaid-config.json"),
-            app_dir.parent()
-                .and_then(|p| p.parent())
-                .map(|p| p.join(".mermaid-config.json"))
-                .unwrap_or_default(),
-        ];
+    // Preset filters run after the named ones (crossref, citeproc) in the order they were listed
+    for filter in filters::resolve(&app, &options.lua_filters, &workspace)? {
+        args.extend(["--lua-filter".to_string(), filter.to_string_lossy().to_string()]);
+    }
+
+    // mermaid-filter reads .mermaid-config.json from its working directory, which is the workspace
+    // (the diagram filter runs it through a wrapper that cd's there)
+    let mermaid_config = options.mermaid.config_json()?;
+    workspace.write(".mermaid-config.json", &mermaid_config)?;
+    let mermaid_env = options.mermaid.filter_env(&target)?;
+    let mermaid_err = workspace.path().join("mermaid-filter.err");
+
+    // Mermaid blocks go through the diagram filter too, which only runs mermaid-filter for diagrams
+    // missing from the render cache
+    let uses_mermaid = workspace::take_filter(&mut args, "mermaid-filter");
+    let mut diagram_env = Vec::new();
+    if uses_mermaid || !options.diagrams.is_empty() {
+        let mut filter = diagrams::DiagramFilter::create(&workspace, &diagrams::cache_dir(&app)?)?;
+        for language in &options.diagrams {
+            filter.add_language(language, &target)?;
+        }
+        if uses_mermaid {
+            let wrapper = workspace.filter_wrapper("mermaid-filter")?;
+            let format = mermaid_env.iter()
+                .find(|(name, _)| *name == "MERMAID_FILTER_FORMAT")
+                .map(|(_, value)| value.as_str())
+                .unwrap_or("png");
+            filter.add("mermaid", &wrapper, format, &format!("{}{:?}", mermaid_config, mermaid_env));
+        }
+
+        // Goes first so pandoc-crossref and other filters see the rendered figures
+        args.splice(0..0, ["--lua-filter".to_string(), filter.path.to_string_lossy().to_string()]);
+        diagram_env = filter.vars;
+    }
 
-        for config_path in possible_paths {
-            if config_path.exists() {
-                let home_config = Path::new(&home).join(".mermaid-config.json");
-                let _ = fs::copy(&config_path, &home_config);
-                break;
+    let working_dir = workspace.working_dir(&options.working_dir, options.input_path.as_deref(), options.output_path.as_deref())?;
+    info!("Working directory: {}", working_dir.display());
+
+    // Relative images and bibliographies resolve against the input, project root and asset folders
+    // whatever the working directory; an explicit --resource-path in the extra arguments wins
+    if !workspace::has_option(&args, "--resource-path") {
+        let mut paths = resources::resource_path(options.input_path.as_deref(), &options.asset_dirs, &working_dir);
+        // Book chapters can sit in their own folders next to their images; searched before the working directory
+        for dir in options.chapters.iter().filter_map(|c| Path::new(c).parent()) {
+            if !paths.iter().any(|p| p == dir) {
+                paths.insert(paths.len() - 1, dir.to_path_buf());
             }
         }
+        args.push(format!("--resource-path={}", resources::join_search_path(&paths)));
     }
 
-    // Detect output format from command to optimize mermaid rendering
-    // For PDF output: use PDF format (best quality, scalable, embeds perfectly)
-    // For HTML/EPUB: use SVG format (scalable, lightweight)
-    let mermaid_format = if command.contains("-t pdf") || command.contains("-t=pdf") {
-        "pdf"  // PDF format embeds perfectly in PDF output with full quality
-    } else {
-        "svg"  // SVG is best for HTML/EPUB (scalable, lightweight)
-    };
-
-    let output = if cfg!(target_os = "windows") {
-        Command::new("cmd")
-            .args(["/C", &command])
-            .env("MERMAID_FILTER_FORMAT", mermaid_format)
-            .env("MERMAID_FILTER_BACKGROUND", "transparent")
-            .output()
-    } else {
-        Command::new("sh")
-            .args(["-c", &command])
-            .env("PATH", &extended_path)
-            // Set working directory to home to avoid read-only filesystem issues
-            .current_dir(&home)
-            // Redirect mermaid-filter error log to temp directory
-            .env("MERMAID_FILTER_ERR", temp_dir.join("mermaid-filter.err"))
-            // Configure mermaid-filter format based on output type
-            .env("MERMAID_FILTER_FORMAT", mermaid_format)
-            .env("MERMAID_FILTER_BACKGROUND", "transparent")
-            .output()
-    };
+    let pandoc = deps::find_in_path("pandoc")
+        .ok_or_else(|| "pandoc not found on PATH".to_string())?;
+    let mut child = Command::new(&pandoc);
+    child.args(&args).env("PATH", &extended_path);
+    workspace.apply(&mut child, &working_dir);
+    if let Some(project) = &options.project_fonts {
+        // kpathsea replaces a trailing separator with the configured default, so system fonts stay visible
+        // to luaotfload/XeTeX name lookups
+        let separator = if cfg!(target_os = "windows") { ';' } else { ':' };
+        child.env("OSFONTDIR", format!("{}{}", project.dir, separator));
+    }
+    child
+        // Keep mermaid-filter's error log and rendered images inside the workspace
+        .env("MERMAID_FILTER_ERR", &mermaid_err)
+        .env("MERMAID_FILTER_LOC", workspace.path().join("img"))
+        // Image format for the target writer, theme, background, width and scale
+        .envs(mermaid_env)
+        .envs(diagram_env);
+    workspace::apply_user_env(&mut child, &options.env)?;
+    let started = SystemTime::now();
+    let output = child.output();
 
     match output {
         Ok(output) => {
             if output.status.success() {
                 info!("Pandoc command completed successfully");
+                if let Some(output_path) = &options.output_path {
+                    // Open on complete and Reveal rely on this, for any output folder
+                    if let Err(e) = scope::allow_output(output_path, &args, started) {
+                        warn!("Conversion output not added to scope: {}", e);
+                    }
+                }
                 Ok(String::from_utf8_lossy(&output.stdout).to_string())
             } else {
                 let stderr = String::from_utf8_lossy(&output.stderr);
                 let stdout = String::from_utf8_lossy(&output.stdout);
-                error!("Pandoc command failed: {}\n{}", stderr, stdout);
-                Err(format!("{}\n{}", stderr, stdout))
+                // The workspace is about to be removed, so surface mermaid-filter's log now
+                let mermaid_log = fs::read_to_string(&mermaid_err).unwrap_or_default();
+                error!("Pandoc command failed: {}\n{}\n{}", stderr, stdout, mermaid_log);
+                Err(format!("{}\n{}\n{}", stderr, stdout, mermaid_log).trim_end().to_string())
             }
         }
         Err(e) => {
@@ -177,6 +329,7 @@ fn run_pandoc(command: String) -> Result<String, String> {
 
 #[tauri::command]
 fn open_file(path: String) -> Result<(), String> {
+    let path = scope::check_openable(&path)?;
     open::that(&path).map_err(|e| format!("Failed to open file: {}", e))
 }
 
@@ -189,6 +342,12 @@ fn get_downloads_path() -> Result<String, String> {
 
 #[tauri::command]
 fn reveal_in_finder(path: String) -> Result<(), String> {
+    let path = scope::check_scoped(&path)?;
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+    let path = path.to_string_lossy().to_string();
+
     #[cfg(target_os = "macos")]
     {
         Command::new("open")
@@ -224,7 +383,7 @@ fn get_app_version() -> String {
 }
 
 // Run a command with streaming output to the frontend
-#[tauri::command]
+// Only called with recipes built by deps.rs; not exposed to the webview
 async fn run_command_with_output(app: AppHandle, command: String, operation: String) -> Result<String, String> {
     let extended_path = get_extended_path();
     let install_id = NEXT_INSTALL_ID.fetch_add(1, Ordering::SeqCst);
@@ -332,8 +491,13 @@ async fn run_command_with_output(app: AppHandle, command: String, operation: Str
     result
 }
 
+pub fn cancel_requests() -> u32 {
+    CANCEL_REQUESTS.load(Ordering::SeqCst)
+}
+
 #[tauri::command]
 fn cancel_all_installs() -> Result<String, String> {
+    CANCEL_REQUESTS.fetch_add(1, Ordering::SeqCst);
     let installs = RUNNING_INSTALLS.lock().map_err(|e| e.to_string())?;
     let count = installs.len();
 
@@ -344,253 +508,9 @@ fn cancel_all_installs() -> Result<String, String> {
     Ok(format!("Cancelling {} operation(s)", count))
 }
 
-// Get the uninstall command for a dependency
-// Uses osascript on macOS for commands requiring admin privileges (shows native password dialog)
-fn get_uninstall_command(name: &str) -> Option<String> {
-    match name {
-        "tectonic" => Some("brew uninstall tectonic 2>&1 || cargo uninstall tectonic 2>&1".to_string()),
-        "texlive" => {
-            if cfg!(target_os = "macos") {
-                Some(r#"ASKPASS_SCRIPT=$(mktemp) && cat > "$ASKPASS_SCRIPT" << 'ASKPASSEOF'
-#!/bin/bash
-osascript -e 'display dialog "Pandoc GUI needs your password to uninstall BasicTeX:" default answer "" with hidden answer buttons {"Cancel","OK"} default button "OK"' -e 'text returned of result' 2>/dev/null
-ASKPASSEOF
-chmod +x "$ASKPASS_SCRIPT" && SUDO_ASKPASS="$ASKPASS_SCRIPT" brew uninstall --cask basictex 2>&1 || brew uninstall --cask mactex 2>&1; EXIT_CODE=$?; rm -f "$ASKPASS_SCRIPT"; exit $EXIT_CODE"#.to_string())
-            } else {
-                Some("brew uninstall --cask basictex 2>&1 || brew uninstall --cask mactex 2>&1".to_string())
-            }
-        },
-        "mermaid-filter" => {
-            if cfg!(target_os = "macos") {
-                // Try without sudo first (works if npm prefix is user-writable), fall back to sudo with askpass
-                Some(r#"npm uninstall -g mermaid-filter 2>&1 || (ASKPASS_SCRIPT=$(mktemp) && cat > "$ASKPASS_SCRIPT" << 'ASKPASSEOF'
-#!/bin/bash
-osascript -e 'display dialog "Pandoc GUI needs your password to uninstall mermaid-filter:" default answer "" with hidden answer buttons {"Cancel","OK"} default button "OK"' -e 'text returned of result' 2>/dev/null
-ASKPASSEOF
-chmod +x "$ASKPASS_SCRIPT" && SUDO_ASKPASS="$ASKPASS_SCRIPT" sudo -A npm uninstall -g mermaid-filter 2>&1; EXIT_CODE=$?; rm -f "$ASKPASS_SCRIPT"; exit $EXIT_CODE)"#.to_string())
-            } else if cfg!(target_os = "linux") {
-                Some("npm uninstall -g mermaid-filter 2>&1 || pkexec npm uninstall -g mermaid-filter 2>&1".to_string())
-            } else {
-                Some("npm uninstall -g mermaid-filter 2>&1".to_string())
-            }
-        },
-        "pandoc-crossref" => Some("brew uninstall pandoc-crossref 2>&1".to_string()),
-        "pandoc" => Some("brew uninstall pandoc 2>&1".to_string()),
-        _ => None,
-    }
-}
-
-// Get the install command for a dependency
-// Uses osascript on macOS for commands requiring admin privileges (shows native password dialog)
-// Uses pkexec on Linux for GUI sudo prompt
-fn get_install_command(name: &str, method: &str) -> Option<String> {
-    match (name, method) {
-        ("tectonic", "brew") => Some("brew install tectonic 2>&1".to_string()),
-        ("tectonic", "cargo") => Some("cargo install tectonic 2>&1".to_string()),
-        ("texlive", "brew") => {
-            if cfg!(target_os = "macos") {
-                // brew cask installs need sudo for the pkg installer
-                // Use SUDO_ASKPASS with osascript to show native password dialog
-                // Homebrew can't run as root, so we provide an askpass helper instead
-                Some(r#"ASKPASS_SCRIPT=$(mktemp) && cat > "$ASKPASS_SCRIPT" << 'ASKPASSEOF'
-#!/bin/bash
-osascript -e 'display dialog "Pandoc GUI needs your password to install BasicTeX:" default answer "" with hidden answer buttons {"Cancel","OK"} default button "OK"' -e 'text returned of result' 2>/dev/null
-ASKPASSEOF
-chmod +x "$ASKPASS_SCRIPT" && SUDO_ASKPASS="$ASKPASS_SCRIPT" brew install --cask basictex 2>&1; EXIT_CODE=$?; rm -f "$ASKPASS_SCRIPT"; exit $EXIT_CODE"#.to_string())
-            } else {
-                Some("brew install --cask basictex 2>&1".to_string())
-            }
-        },
-        ("texlive", "apt") => {
-            if cfg!(target_os = "linux") {
-                // Use pkexec for GUI password prompt on Linux
-                Some("pkexec apt install -y texlive-latex-base texlive-fonts-recommended texlive-latex-extra 2>&1".to_string())
-            } else {
-                Some("sudo apt install texlive-latex-base texlive-fonts-recommended texlive-latex-extra 2>&1".to_string())
-            }
-        },
-        ("mermaid-filter", "npm") => {
-            if cfg!(target_os = "macos") {
-                // Try without sudo first (works if npm prefix is user-writable), fall back to sudo with askpass
-                Some(r#"npm install -g mermaid-filter 2>&1 || (ASKPASS_SCRIPT=$(mktemp) && cat > "$ASKPASS_SCRIPT" << 'ASKPASSEOF'
-#!/bin/bash
-osascript -e 'display dialog "Pandoc GUI needs your password to install mermaid-filter:" default answer "" with hidden answer buttons {"Cancel","OK"} default button "OK"' -e 'text returned of result' 2>/dev/null
-ASKPASSEOF
-chmod +x "$ASKPASS_SCRIPT" && SUDO_ASKPASS="$ASKPASS_SCRIPT" sudo -A npm install -g mermaid-filter 2>&1; EXIT_CODE=$?; rm -f "$ASKPASS_SCRIPT"; exit $EXIT_CODE)"#.to_string())
-            } else if cfg!(target_os = "linux") {
-                Some("npm install -g mermaid-filter 2>&1 || pkexec npm install -g mermaid-filter 2>&1".to_string())
-            } else {
-                Some("npm install -g mermaid-filter 2>&1".to_string())
-            }
-        },
-        ("pandoc-crossref", "brew") => Some("brew install pandoc-crossref 2>&1".to_string()),
-        ("pandoc", "brew") => Some("brew install pandoc 2>&1".to_string()),
-        _ => None,
-    }
-}
-
-#[tauri::command]
-async fn install_dependency(app: AppHandle, name: String, method: String) -> Result<String, String> {
-    info!("Installing dependency: {} via {}", name, method);
-    let command = get_install_command(&name, &method)
-        .ok_or_else(|| format!("Unknown install method {} for {}", method, name))?;
-    info!("Install command: {}", command);
-
-    run_command_with_output(app, command, format!("Installing {}", name)).await
-}
-
-#[tauri::command]
-async fn uninstall_dependency(app: AppHandle, name: String) -> Result<String, String> {
-    info!("Uninstalling dependency: {}", name);
-    let command = get_uninstall_command(&name)
-        .ok_or_else(|| format!("Unknown dependency: {}", name))?;
-    info!("Uninstall command: {}", command);
-
-    run_command_with_output(app, command, format!("Uninstalling {}", name)).await
-}
-
-#[tauri::command]
-async fn reinstall_dependency(app: AppHandle, name: String, method: String) -> Result<String, String> {
-    // First uninstall
-    let uninstall_cmd = get_uninstall_command(&name)
-        .ok_or_else(|| format!("Unknown dependency: {}", name))?;
-
-    let _ = run_command_with_output(app.clone(), uninstall_cmd, format!("Uninstalling {}", name)).await;
-
-    // Then install
-    let install_cmd = get_install_command(&name, &method)
-        .ok_or_else(|| format!("Unknown install method {} for {}", method, name))?;
-
-    run_command_with_output(app, install_cmd, format!("Reinstalling {}", name)).await
-}
-
 #[tauri::command]
-fn file_exists(path: String) -> bool {
-    std::path::Path::new(&path).exists()
-}
-
-#[tauri::command]
-fn write_dark_mode_header() -> Result<String, String> {
-    use std::io::Write;
-
-    let temp_dir = env::temp_dir();
-    let header_path = temp_dir.join("pandoc-dark-mode.tex");
-
-    let header_content = r#"\usepackage{pagecolor}
-\usepackage{xcolor}
-\definecolor{darkbg}{HTML}{1e1e2e}
-\definecolor{lighttext}{HTML}{cdd6f4}
-\pagecolor{darkbg}
-\color{lighttext}
-"#;
-
-    let mut file = std::fs::File::create(&header_path)
-        .map_err(|e| format!("Failed to create header file: {}", e))?;
-
-    file.write_all(header_content.as_bytes())
-        .map_err(|e| format!("Failed to write header file: {}", e))?;
-
-    Ok(header_path.to_string_lossy().to_string())
-}
-
-// Write a header file to fix Unicode box-drawing characters for monospace fonts
-#[tauri::command]
-fn write_unicode_header() -> Result<String, String> {
-    use std::io::Write;
-
-    let temp_dir = env::temp_dir();
-    let header_path = temp_dir.join("pandoc-unicode-fix.tex");
-
-    // Use fontspec to set fallback fonts for missing Unicode characters
-    // Menlo on macOS has good Unicode coverage including box-drawing chars
-    let header_content = r#"\usepackage{fontspec}
-\directlua{
-  luaotfload.add_fallback("monofallback", {
-    "Menlo:mode=harf;",
-    "DejaVu Sans Mono:mode=harf;",
-    "Apple Symbols:mode=harf;",
-  })
-}
-\setmonofont{Noto Mono}[RawFeature={fallback=monofallback}]
-"#;
-
-    let mut file = std::fs::File::create(&header_path)
-        .map_err(|e| format!("Failed to create header file: {}", e))?;
-
-    file.write_all(header_content.as_bytes())
-        .map_err(|e| format!("Failed to write header file: {}", e))?;
-
-    Ok(header_path.to_string_lossy().to_string())
-}
-
-#[tauri::command]
-fn list_system_fonts() -> Result<Vec<String>, String> {
-    use std::collections::HashSet;
-
-    // Platform-specific font listing
-    let output = if cfg!(target_os = "macos") {
-        // macOS: Try fc-list first, fall back to atsutil
-        Command::new("fc-list")
-            .args([":", "family"])
-            .output()
-            .or_else(|_| {
-                // Fallback: use atsutil on macOS (always available)
-                Command::new("sh")
-                    .args(["-c", "atsutil fonts -list | grep -v '^$' | sort -u"])
-                    .output()
-            })
-    } else if cfg!(target_os = "linux") {
-        // Linux: fc-list is standard on most distros
-        Command::new("fc-list")
-            .args([":", "family"])
-            .output()
-    } else if cfg!(target_os = "windows") {
-        // Windows: Use PowerShell with proper assembly loading
-        Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-Command",
-                "Add-Type -AssemblyName System.Drawing; (New-Object System.Drawing.Text.InstalledFontCollection).Families | ForEach-Object { $_.Name }"
-            ])
-            .output()
-    } else {
-        return Err("Unsupported platform".to_string());
-    };
-
-    match output {
-        Ok(output) => {
-            if output.status.success() {
-                let text = String::from_utf8_lossy(&output.stdout);
-                let mut fonts: HashSet<String> = HashSet::new();
-
-                for line in text.lines() {
-                    // fc-list may have multiple families separated by commas
-                    for part in line.split(',') {
-                        let font = part.trim().to_string();
-                        // Filter out empty lines, hidden fonts (starting with .), and system prefixes
-                        if !font.is_empty()
-                            && !font.starts_with('.')
-                            && !font.starts_with('#')
-                            && font.len() > 1
-                        {
-                            fonts.insert(font);
-                        }
-                    }
-                }
-
-                let mut result: Vec<String> = fonts.into_iter().collect();
-                result.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()));
-                Ok(result)
-            } else {
-                // Return empty list instead of error - font selection is optional
-                Ok(vec![])
-            }
-        }
-        Err(_) => {
-            // Return empty list if command fails
-            Ok(vec![])
-        }
-    }
+fn file_exists(path: String) -> Result<bool, String> {
+    Ok(scope::check_scoped(&path)?.exists())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -601,6 +521,9 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_http::init())
         .setup(|app| {
+            // Clear out job workspaces left behind by earlier crashes
+            workspace::sweep_stale_workspaces();
+
             // Create custom menu
             let about_item = MenuItem::with_id(app, "about", "About Pandoc GUI", true, None::<&str>)?;
             let quit_item = PredefinedMenuItem::quit(app, Some("Quit"))?;
@@ -651,7 +574,7 @@ pub fn run() {
             )?;
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![run_pandoc, open_file, check_command, list_system_fonts, file_exists, write_dark_mode_header, write_unicode_header, install_dependency, cancel_all_installs, uninstall_dependency, reinstall_dependency, run_command_with_output, get_downloads_path, reveal_in_finder, get_app_version])
+        .invoke_handler(tauri::generate_handler![run_pandoc, open_file, scope::select_input_file, scope::select_output_dir, deps::detect_dependency, fonts::list_system_fonts, font_cache::list_font_catalog, fonts::list_project_fonts, fonts::preview_unicode_header, fonts::scan_font_coverage, file_exists, themes::list_output_themes, themes::save_output_theme, themes::delete_output_theme, deps::install_dependency, cancel_all_installs, deps::uninstall_dependency, deps::reinstall_dependency, get_downloads_path, reveal_in_finder, get_app_version, doctor::run_doctor, doctor::doctor_report_markdown, diagrams::get_diagram_cache_size, diagrams::clear_diagram_cache, filters::list_lua_filters, filters::add_lua_filter, filters::remove_lua_filter, citations::check_citations, templates::list_templates, templates::import_template, templates::export_default_template, templates::delete_template, templates::inspect_template_variables, community::list_community_templates, community::install_community_template, community::uninstall_community_template, projects::open_project, projects::rescan_project, projects::save_project, projects::watch_project, projects::unwatch_project, formats::detect_input_format])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,123 @@
+use log::warn;
+use serde::Deserialize;
+
+// Themes mermaid ships with
+const THEMES: &[&str] = &["default", "forest", "dark", "neutral", "base"];
+
+// Output formats that can only embed raster images (mermaid-filter's SVG/PDF output is dropped
+// or rendered blank by Word, PowerPoint and LibreOffice)
+const RASTER_ONLY_FORMATS: &[&str] = &["docx", "pptx", "odt", "rtf"];
+
+// Per-preset mermaid rendering settings; unset fields keep mermaid-filter's defaults
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MermaidOptions {
+    pub theme: Option<String>,
+    // "auto", "png", "svg" or "pdf"
+    pub format: Option<String>,
+    pub scale: Option<f32>,
+    pub width: Option<u32>,
+    // "transparent", "white" or a CSS color
+    pub background: Option<String>,
+    pub font_family: Option<String>,
+    pub css: Option<String>,
+}
+
+// Pandoc writer from `-t`/`--to` in the command, for jobs that don't pass the format explicitly
+pub fn target_format(args: &[String]) -> Option<String> {
+    let mut tokens = args.iter().map(String::as_str);
+    while let Some(token) = tokens.next() {
+        let value = match token {
+            "-t" | "--to" | "-w" | "--write" => tokens.next(),
+            _ => token.strip_prefix("--to=").or_else(|| token.strip_prefix("-t=")),
+        };
+        if let Some(value) = value {
+            // Drop extensions (markdown+smart)
+            return Some(value.split(['+', '-']).next().unwrap_or(value).to_string());
+        }
+    }
+    None
+}
+
+// Image format mermaid-filter should render for the target writer
+// auto: vector PDF for LaTeX/PDF, SVG for HTML/EPUB, PNG for office formats and everything else
+pub fn image_format(requested: Option<&str>, target: &str) -> &'static str {
+    let raster_only = RASTER_ONLY_FORMATS.contains(&target);
+    let explicit = match requested {
+        Some("png") => Some("png"),
+        Some("svg") => Some("svg"),
+        Some("pdf") => Some("pdf"),
+        _ => None,
+    };
+
+    match explicit {
+        Some(format) if raster_only && format != "png" => {
+            warn!("{} output can't embed {} diagrams, using PNG", target, format);
+            "png"
+        },
+        Some(format) => format,
+        None => match target {
+            "pdf" | "latex" | "beamer" => "pdf",
+            "html" | "html5" | "html4" | "epub" | "epub3" | "epub2" | "revealjs" => "svg",
+            _ => "png",
+        },
+    }
+}
+
+impl MermaidOptions {
+    // mermaid config written to the job workspace as .mermaid-config.json
+    // htmlLabels stay off because foreignObject labels render blank in PDF and office output
+    pub fn config_json(&self) -> Result<String, String> {
+        let theme = match self.theme.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(theme) if THEMES.contains(&theme) => theme,
+            Some(theme) => return Err(format!("Unknown mermaid theme '{}': use one of {}", theme, THEMES.join(", "))),
+            None => "default",
+        };
+        let font_family = self.font_family.as_deref()
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .unwrap_or("Arial, Helvetica, sans-serif");
+
+        let mut config = serde_json::json!({
+            "theme": theme,
+            "fontFamily": font_family,
+            "themeVariables": { "fontFamily": font_family },
+            "flowchart": { "htmlLabels": false, "useMaxWidth": true, "curve": "linear" },
+            "sequence": { "htmlLabels": false },
+            "classDiagram": { "htmlLabels": false },
+            "stateDiagram": { "htmlLabels": false },
+            "state": { "htmlLabels": false },
+            "securityLevel": "loose",
+            "startOnLoad": true,
+            "logLevel": "fatal"
+        });
+        if let Some(css) = self.css.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            config["themeCSS"] = serde_json::Value::String(css.to_string());
+        }
+
+        serde_json::to_string_pretty(&config)
+            .map_err(|e| format!("Failed to serialize mermaid config: {}", e))
+    }
+
+    // MERMAID_FILTER_* variables for this job (the error log and image folder are set by run_pandoc)
+    pub fn filter_env(&self, target: &str) -> Result<Vec<(&'static str, String)>, String> {
+        let mut vars = vec![
+            ("MERMAID_FILTER_FORMAT", image_format(self.format.as_deref(), target).to_string()),
+            ("MERMAID_FILTER_THEME", self.theme.clone().filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "default".to_string())),
+            ("MERMAID_FILTER_BACKGROUND", self.background.clone().filter(|b| !b.trim().is_empty()).unwrap_or_else(|| "transparent".to_string())),
+        ];
+        if let Some(width) = self.width {
+            if width == 0 {
+                return Err("Mermaid width must be greater than 0".to_string());
+            }
+            vars.push(("MERMAID_FILTER_WIDTH", width.to_string()));
+        }
+        if let Some(scale) = self.scale {
+            if !(scale > 0.0 && scale <= 10.0) {
+                return Err(format!("Mermaid scale must be between 0 and 10, got {}", scale));
+            }
+            vars.push(("MERMAID_FILTER_SCALE", scale.to_string()));
+        }
+        Ok(vars)
+    }
+}
@@ -0,0 +1,569 @@
+use std::cmp::Ordering as CmpOrdering;
+use std::fs;
+use std::iter::Peekable;
+use std::path::{Component, Path, PathBuf};
+use std::str::Chars;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+use log::{info, warn};
+
+use crate::scope;
+
+// Book projects the user opened, keyed by root folder, so a hand-made chapter order survives restarts
+const PROJECTS_FILE: &str = "projects.json";
+
+// Files that define the chapter order, checked in this order (Jupyter Book, GitBook, mdBook)
+const ORDER_FILES: &[&str] = &["_toc.yml", "SUMMARY.md", "src/SUMMARY.md"];
+
+// Extensions tried for _toc.yml entries, which leave them out. Only Markdown is read; the others are
+// resolved so they can be reported as unsupported instead of as missing
+const CHAPTER_EXTENSIONS: &[&str] = &["md", "markdown", "ipynb", "rst", "tex"];
+
+// Folders skipped when collecting chapters from a project without an order file
+const SKIPPED_DIRS: &[&str] = &["node_modules", "_build", "_book", "book", "target", "dist"];
+
+// How deep below the root chapters are collected when there is no order file
+const MAX_CHAPTER_DEPTH: usize = 2;
+
+// How often the watcher compares modification times
+const WATCH_INTERVAL: Duration = Duration::from_millis(1000);
+
+lazy_static::lazy_static! {
+    // Stop flag of the running watcher; only one project is watched at a time
+    static ref WATCHER: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BookProject {
+    // Absolute, canonical project folder
+    pub root: String,
+    pub name: String,
+    // Chapter files relative to the root, in the order pandoc concatenates them
+    pub chapters: Vec<String>,
+    // _toc.yml/SUMMARY.md the order was read from; None when the order is managed in the app
+    #[serde(default)]
+    pub order_file: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectListing {
+    #[serde(flatten)]
+    pub project: BookProject,
+    // Absolute chapter paths in order, as they go on the pandoc command line
+    pub paths: Vec<String>,
+    // Chapters listed in the order file or saved order that no longer exist
+    pub missing: Vec<String>,
+    // Chapters that exist but aren't Markdown (notebooks, reST, LaTeX), left out of the book
+    pub unsupported: Vec<String>,
+}
+
+fn projects_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(PROJECTS_FILE))
+        .map_err(|e| format!("Could not find app data directory: {}", e))
+}
+
+fn load_user_projects(app: &AppHandle) -> Result<Vec<BookProject>, String> {
+    let path = projects_path(app)?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_user_projects(app: &AppHandle, projects: &[BookProject]) -> Result<(), String> {
+    let path = projects_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(projects)
+        .map_err(|e| format!("Failed to serialize projects: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn store_project(app: &AppHandle, project: &BookProject) -> Result<(), String> {
+    let mut projects = load_user_projects(app)?;
+    projects.retain(|p| p.root != project.root);
+    projects.push(project.clone());
+    save_user_projects(app, &projects)
+}
+
+// Only folders picked in open_project are stored, so the webview can't name arbitrary roots
+fn find_project(app: &AppHandle, root: &str) -> Result<BookProject, String> {
+    load_user_projects(app)?
+        .into_iter()
+        .find(|p| p.root == root)
+        .ok_or_else(|| format!("Project not found: {}", root))
+}
+
+// Relative chapter path with forward slashes; rejects absolute paths and anything leaving the root
+fn normalize_chapter(chapter: &str) -> Option<String> {
+    let path = Path::new(chapter.trim());
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn is_chapter_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| ext == "md" || ext == "markdown")
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+        digits.push(c);
+        chars.next();
+    }
+    digits
+}
+
+// Compare digit runs by value so "2-setup.md" sorts before "10-appendix.md"
+fn natural_cmp(a: &str, b: &str) -> CmpOrdering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return CmpOrdering::Equal,
+            (None, Some(_)) => return CmpOrdering::Less,
+            (Some(_), None) => return CmpOrdering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (take_digits(&mut a), take_digits(&mut b));
+                let (x_trimmed, y_trimmed) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let ordering = x_trimmed.len().cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ordering != CmpOrdering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != CmpOrdering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+// Markdown files under the root in natural order; used when the project has no order file
+fn collect_chapters(root: &Path) -> Vec<String> {
+    fn walk(dir: &Path, prefix: &str, depth: usize, found: &mut Vec<String>) {
+        let Ok(entries) = fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let relative = format!("{}{}", prefix, name);
+            if path.is_dir() {
+                if depth < MAX_CHAPTER_DEPTH && !SKIPPED_DIRS.contains(&name.as_str()) {
+                    walk(&path, &format!("{}/", relative), depth + 1, found);
+                }
+            } else if is_chapter_file(&path) {
+                found.push(relative);
+            }
+        }
+    }
+
+    let mut chapters = Vec::new();
+    walk(root, "", 0, &mut chapters);
+    // Files in the root come before those in subfolders, then natural order
+    chapters.sort_by(|a, b| {
+        a.contains('/').cmp(&b.contains('/')).then_with(|| natural_cmp(a, b))
+    });
+    chapters
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value.strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+// Jupyter Book _toc.yml: `root:` and `file:` entries in document order, relative to the root and
+// usually without an extension. A line scan is enough for the format and avoids a YAML dependency
+fn toc_yml_chapters(source: &str, root: &Path) -> Vec<String> {
+    let mut chapters = Vec::new();
+    for line in source.lines() {
+        let line = line.split(" #").next().unwrap_or("").trim();
+        let line = line.strip_prefix("- ").unwrap_or(line).trim();
+        let Some(value) = line.strip_prefix("root:").or_else(|| line.strip_prefix("file:")) else { continue };
+        let Some(file) = normalize_chapter(unquote(value)) else { continue };
+
+        let resolved = if Path::new(&file).extension().is_some() {
+            file
+        } else {
+            CHAPTER_EXTENSIONS.iter()
+                .map(|ext| format!("{}.{}", file, ext))
+                .find(|candidate| root.join(candidate).is_file())
+                .unwrap_or_else(|| format!("{}.md", file))
+        };
+        if !chapters.contains(&resolved) {
+            chapters.push(resolved);
+        }
+    }
+    chapters
+}
+
+// GitBook/mdBook SUMMARY.md: link targets in order, relative to the SUMMARY's folder (src/ for mdBook)
+fn summary_chapters(source: &str, base: &str) -> Vec<String> {
+    let mut chapters = Vec::new();
+    for line in source.lines() {
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            rest = &rest[start + 2..];
+            let Some(end) = rest.find(')') else { break };
+            let target = rest[..end].trim();
+            rest = &rest[end..];
+
+            // <...> targets may contain spaces; otherwise a link title can follow the target
+            let target = match target.strip_prefix('<') {
+                Some(inner) => inner.split('>').next().unwrap_or(""),
+                None => target.split_whitespace().next().unwrap_or(""),
+            };
+            let target = target.split('#').next().unwrap_or("");
+            if target.is_empty() || target.contains("://") || target.starts_with("mailto:") {
+                continue;
+            }
+            let Some(chapter) = normalize_chapter(&format!("{}{}", base, target)) else { continue };
+            if !chapters.contains(&chapter) {
+                chapters.push(chapter);
+            }
+        }
+    }
+    chapters
+}
+
+// First order file present in the project and the chapters it lists
+fn read_order_file(root: &Path) -> Result<Option<(String, Vec<String>)>, String> {
+    let Some(file) = ORDER_FILES.iter().find(|file| root.join(file).is_file()) else { return Ok(None) };
+    let path = root.join(file);
+    let source = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let chapters = if file.ends_with(".yml") {
+        toc_yml_chapters(&source, root)
+    } else {
+        let base = file.rsplit_once('/').map(|(dir, _)| format!("{}/", dir)).unwrap_or_default();
+        summary_chapters(&source, &base)
+    };
+    Ok(Some((file.to_string(), chapters)))
+}
+
+// Chapter order for a freshly opened or rescanned project: the order file when there is one,
+// otherwise the Markdown files in natural order
+fn detect_project(root: &Path) -> Result<BookProject, String> {
+    let name = root.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().to_string());
+
+    let (order_file, chapters) = match read_order_file(root)? {
+        Some((file, chapters)) => (Some(file), chapters),
+        None => (None, collect_chapters(root)),
+    };
+    if chapters.is_empty() {
+        return Err(format!("No chapters found in {}", root.display()));
+    }
+
+    Ok(BookProject {
+        root: root.to_string_lossy().to_string(),
+        name,
+        chapters,
+        order_file,
+    })
+}
+
+// An order file in the project wins over a stored order, since editing it is how the book is reordered
+fn refresh(project: BookProject) -> Result<BookProject, String> {
+    let root = PathBuf::from(&project.root);
+    match read_order_file(&root)? {
+        Some((file, chapters)) => Ok(BookProject { chapters, order_file: Some(file), ..project }),
+        None if project.order_file.is_some() => detect_project(&root),
+        None => Ok(project),
+    }
+}
+
+fn listing(project: BookProject) -> ProjectListing {
+    let root = Path::new(&project.root);
+    let mut paths = Vec::new();
+    let mut missing = Vec::new();
+    let mut unsupported = Vec::new();
+    for chapter in &project.chapters {
+        match fs::canonicalize(root.join(chapter)) {
+            Ok(path) if path.starts_with(root) && path.is_file() && is_chapter_file(&path) => paths.push(path),
+            Ok(path) if path.starts_with(root) && path.is_file() => unsupported.push(chapter.clone()),
+            _ => missing.push(chapter.clone()),
+        }
+    }
+
+    // Chapters are read by the citation check and opened from the chapter list
+    scope::allow_project(root, &paths);
+    ProjectListing {
+        paths: paths.iter().map(|p| scope::simplified(p).to_string_lossy().to_string()).collect(),
+        project,
+        missing,
+        unsupported,
+    }
+}
+
+// Folder picker for a book project; a stored project reopens with its saved order
+#[tauri::command]
+pub async fn open_project(app: AppHandle) -> Result<Option<ProjectListing>, String> {
+    let Some(picked) = app.dialog().file().blocking_pick_folder() else { return Ok(None) };
+    let path = picked.into_path()
+        .map_err(|e| format!("Invalid folder selection: {}", e))?;
+    let root = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+    // The picked folder is in scope like select_output_dir's, even if no chapters are found in it
+    scope::allow_project(&root, &[]);
+
+    let app_task = app.clone();
+    let project = tokio::task::spawn_blocking(move || {
+        let stored = find_project(&app_task, &root.to_string_lossy()).ok();
+        let project = match stored {
+            Some(project) => refresh(project)?,
+            None => detect_project(&root)?,
+        };
+        store_project(&app_task, &project)?;
+        Ok::<_, String>(project)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))??;
+
+    info!("Opened book project {} with {} chapters", project.root, project.chapters.len());
+    Ok(Some(listing(project)))
+}
+
+// Re-read the order file, or collect the Markdown files again for a project without one
+#[tauri::command]
+pub async fn rescan_project(app: AppHandle, root: String) -> Result<ProjectListing, String> {
+    tokio::task::spawn_blocking(move || {
+        let project = find_project(&app, &root)?;
+        let project = detect_project(Path::new(&project.root))?;
+        store_project(&app, &project)?;
+        Ok(listing(project))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+// Store a chapter order edited in the app; from then on the order is managed here, not by the order file
+#[tauri::command]
+pub async fn save_project(app: AppHandle, root: String, chapters: Vec<String>) -> Result<ProjectListing, String> {
+    tokio::task::spawn_blocking(move || {
+        let stored = find_project(&app, &root)?;
+        let mut normalized = Vec::new();
+        for chapter in &chapters {
+            let chapter = normalize_chapter(chapter)
+                .ok_or_else(|| format!("Chapter must be a path inside the project: {}", chapter))?;
+            if !normalized.contains(&chapter) {
+                normalized.push(chapter);
+            }
+        }
+        if normalized.is_empty() {
+            return Err("A project needs at least one chapter".to_string());
+        }
+
+        let project = BookProject { chapters: normalized, order_file: None, ..stored };
+        store_project(&app, &project)?;
+        Ok(listing(project))
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// The root and the folders holding chapters, as "./" and "<folder>/"; their modification time
+// changes when a file is added, removed or renamed in them
+fn chapter_folders(project: &BookProject) -> Vec<String> {
+    let mut folders = vec!["./".to_string()];
+    for file in project.chapters.iter().chain(project.order_file.iter()) {
+        if let Some((folder, _)) = file.rsplit_once('/') {
+            let folder = format!("{}/", folder);
+            if !folders.contains(&folder) {
+                folders.push(folder);
+            }
+        }
+    }
+    folders
+}
+
+// Modification times of the chapters, the order file and their folders, compared on every tick
+fn snapshot(project: &BookProject) -> Vec<(String, Option<SystemTime>)> {
+    let root = Path::new(&project.root);
+    project.chapters.iter()
+        .chain(project.order_file.iter())
+        .cloned()
+        .chain(chapter_folders(project))
+        .map(|file| {
+            let time = modified(&root.join(&file));
+            (file, time)
+        })
+        .collect()
+}
+
+// After a folder changed: re-read the order file (one may have been added), or add the Markdown files
+// that appeared since the last scan. Chapters the user left out of the book stay out
+fn rescan(project: BookProject, known: &mut Vec<String>) -> Result<BookProject, String> {
+    let mut project = refresh(project)?;
+    if project.order_file.is_none() {
+        let found = collect_chapters(Path::new(&project.root));
+        for chapter in &found {
+            if !known.contains(chapter) && !project.chapters.contains(chapter) {
+                project.chapters.push(chapter.clone());
+            }
+        }
+        *known = found;
+    }
+    Ok(project)
+}
+
+fn stop_watcher() {
+    if let Some(stop) = WATCHER.lock().unwrap().take() {
+        stop.store(true, Ordering::SeqCst);
+    }
+}
+
+// Poll the project's chapters and emit project-changed when any of them (or the order file) changes,
+// or when a chapter file is added to the project's folders. Starting a watch stops the previous one
+#[tauri::command]
+pub fn watch_project(app: AppHandle, root: String) -> Result<(), String> {
+    let mut project = find_project(&app, &root)?;
+    stop_watcher();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    *WATCHER.lock().unwrap() = Some(stop.clone());
+    info!("Watching book project {}", root);
+
+    std::thread::spawn(move || {
+        let mut last = snapshot(&project);
+        let mut known = collect_chapters(Path::new(&project.root));
+        while !stop.load(Ordering::SeqCst) {
+            std::thread::sleep(WATCH_INTERVAL);
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let current = snapshot(&project);
+            if current == last {
+                continue;
+            }
+            let changed: Vec<String> = current.iter()
+                .filter(|entry| !last.contains(entry))
+                .map(|(file, _)| file.clone())
+                .collect();
+
+            // A changed order file or folder can add or drop chapters, so they are watched from now on
+            let order_changed = project.order_file.as_ref().is_some_and(|file| changed.contains(file));
+            let folder_changed = changed.iter().any(|entry| entry.ends_with('/'));
+            let mut listing_json = serde_json::Value::Null;
+            if order_changed || folder_changed {
+                let rescanned = rescan(project.clone(), &mut known).and_then(|p| {
+                    if order_changed || p.chapters != project.chapters || p.order_file != project.order_file {
+                        store_project(&app, &p).map(|_| Some(p))
+                    } else {
+                        Ok(None)
+                    }
+                });
+                match rescanned {
+                    Ok(Some(rescanned)) => {
+                        project = rescanned;
+                        listing_json = serde_json::to_value(listing(project.clone())).unwrap_or_default();
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to re-read chapter order: {}", e),
+                }
+            }
+            last = snapshot(&project);
+
+            // Outputs written next to the chapters also touch the folders; only chapter changes count
+            let changed: Vec<String> = changed.into_iter().filter(|entry| !entry.ends_with('/')).collect();
+            if changed.is_empty() && listing_json.is_null() {
+                continue;
+            }
+
+            let _ = app.emit("project-changed", serde_json::json!({
+                "root": project.root,
+                "changed": changed,
+                "project": listing_json,
+            }));
+        }
+        info!("Stopped watching book project {}", project.root);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unwatch_project() {
+    stop_watcher();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        let mut names = vec!["10-appendix.md", "2-setup.md", "Intro.md", "1-basics.md", "02-setup.md", "chapter10.md", "chapter9.md"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["1-basics.md", "2-setup.md", "02-setup.md", "10-appendix.md", "chapter9.md", "chapter10.md", "Intro.md"]);
+    }
+
+    #[test]
+    fn chapters_stay_below_the_root() {
+        assert_eq!(normalize_chapter("./src/intro.md").as_deref(), Some("src/intro.md"));
+        assert_eq!(normalize_chapter("../outside.md"), None);
+        assert_eq!(normalize_chapter("/etc/passwd"), None);
+    }
+
+    #[test]
+    fn toc_yml_entries_in_order() {
+        let toc = "format: jb-book\nroot: intro\nchapters:\n- file: 'chapters/one'  # first\n- file: \"chapters/two.ipynb\"\n- url: https://example.org\n- file: intro\n";
+        let root = Path::new("/nonexistent-project-root");
+        assert_eq!(toc_yml_chapters(toc, root), vec!["intro.md", "chapters/one.md", "chapters/two.ipynb"]);
+    }
+
+    #[test]
+    fn summary_links_in_order() {
+        let summary = "# Summary\n\n[Introduction](README.md)\n\n- [Setup](setup.md \"Title\")\n  - [Details](<ch 2/b.md>#part)\n- [Site](https://example.org)\n- [Again](setup.md)\n- [Outside](../x.md)\n";
+        assert_eq!(summary_chapters(summary, "src/"), vec!["src/README.md", "src/setup.md", "src/ch 2/b.md"]);
+    }
+
+    #[test]
+    fn watches_the_root_and_chapter_folders() {
+        let project = BookProject {
+            root: "/nonexistent-project-root".to_string(),
+            name: "book".to_string(),
+            chapters: vec!["intro.md".to_string(), "part 1/a.md".to_string(), "part 1/b.md".to_string(), "part 2/c.md".to_string()],
+            order_file: Some("src/SUMMARY.md".to_string()),
+        };
+        assert_eq!(chapter_folders(&project), vec!["./", "part 1/", "part 2/", "src/"]);
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+use log::warn;
+
+// Files/folders that mark the top of a document project (repository or book root)
+const PROJECT_MARKERS: &[&str] = &[".git", ".hg", ".svn", "_quarto.yml", "_bookdown.yml", "_toc.yml", "book.toml"];
+
+// How far above the input to look for a project root
+const MAX_PROJECT_DEPTH: usize = 8;
+
+// Nearest ancestor of the input's folder containing a project marker; stops at the home directory
+pub fn find_project_root(input_dir: &Path) -> Option<PathBuf> {
+    let home = dirs::home_dir();
+    input_dir
+        .ancestors()
+        .take(MAX_PROJECT_DEPTH)
+        .take_while(|dir| home.as_deref() != Some(*dir))
+        .find(|dir| PROJECT_MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .map(Path::to_path_buf)
+}
+
+// Folders pandoc searches for images, bibliographies and other relative resources, in order:
+// the input's folder, the project root, preset asset folders (relative to the input), then the
+// working directory, which pandoc only searches by default when no --resource-path is given
+pub fn resource_path(input: Option<&str>, asset_dirs: &[String], working_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut push = |path: PathBuf| {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    };
+
+    let input_dir = input.and_then(|p| Path::new(p).parent()).filter(|dir| dir.is_dir());
+    if let Some(input_dir) = input_dir {
+        push(input_dir.to_path_buf());
+        if let Some(root) = find_project_root(input_dir) {
+            push(root);
+        }
+    }
+
+    for dir in asset_dirs.iter().map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        let path = match input_dir {
+            Some(input_dir) => input_dir.join(dir),
+            None => PathBuf::from(dir),
+        };
+        if path.is_dir() {
+            push(path);
+        } else {
+            warn!("Asset folder not found, skipping: {}", path.display());
+        }
+    }
+
+    push(working_dir.to_path_buf());
+    paths
+}
+
+// Join with the platform's search path separator, as --resource-path expects
+pub fn join_search_path(paths: &[PathBuf]) -> String {
+    let separator = if cfg!(target_os = "windows") { ";" } else { ":" };
+    paths.iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
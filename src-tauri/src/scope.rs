@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+// Paths the webview may open, reveal or probe: files and folders the user picked in a dialog and
+// files produced by conversions. Everything else is rejected, so an injected script can't use
+// these commands to launch programs or probe the disk
+#[derive(Default)]
+struct PathScope {
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+}
+
+lazy_static::lazy_static! {
+    static ref SCOPE: Mutex<PathScope> = Mutex::new(PathScope::default());
+}
+
+// Extensions open_file refuses even when in scope, since the OS would run them rather than show them
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "app", "appimage", "bat", "cmd", "com", "command", "cpl", "desktop", "exe", "jar", "js", "jse",
+    "lnk", "msi", "msp", "pif", "ps1", "reg", "scr", "sh", "url", "vbe", "vbs", "webloc", "ws", "wsf",
+];
+
+// canonicalize() on Windows returns \\?\C:\... paths, which Explorer and ShellExecute don't accept
+pub fn simplified(path: &Path) -> PathBuf {
+    let text = path.to_string_lossy();
+    match text.strip_prefix(r"\\?\") {
+        Some(rest) if !rest.starts_with("UNC\\") => PathBuf::from(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+// Absolute, canonical form of a path from the webview; paths that don't exist yet (a conversion's
+// output) are resolved through their parent directory
+fn resolve(path: &str) -> Result<PathBuf, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("No path given".to_string());
+    }
+    if path.contains("://") || path.starts_with("file:") || path.starts_with("mailto:") {
+        return Err(format!("URLs are not allowed: {}", path));
+    }
+
+    let path = Path::new(path);
+    if !path.is_absolute() {
+        return Err(format!("Path must be absolute: {}", path.display()));
+    }
+
+    if path.exists() {
+        return fs::canonicalize(path)
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e));
+    }
+
+    let parent = path.parent()
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    let name = path.file_name()
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    fs::canonicalize(parent)
+        .map(|parent| parent.join(name))
+        .map_err(|e| format!("Failed to resolve {}: {}", parent.display(), e))
+}
+
+fn allow_file(path: &Path) {
+    let mut scope = SCOPE.lock().unwrap();
+    scope.files.insert(path.to_path_buf());
+}
+
+fn allow_dir(path: &Path) {
+    let mut scope = SCOPE.lock().unwrap();
+    scope.dirs.insert(path.to_path_buf());
+}
+
+// Downloads is where update installers are saved, so it is always in scope
+fn in_allowed_dir(path: &Path) -> bool {
+    let Some(parent) = path.parent() else { return false };
+    let downloads = dirs::download_dir().and_then(|dir| fs::canonicalize(dir).ok());
+    SCOPE.lock().unwrap().dirs.contains(parent) || downloads.as_deref() == Some(parent)
+}
+
+fn is_allowed_file(path: &Path) -> bool {
+    SCOPE.lock().unwrap().files.contains(path)
+}
+
+fn resolve_scoped(path: &str) -> Result<PathBuf, String> {
+    let resolved = resolve(path)?;
+    if is_allowed_file(&resolved) || in_allowed_dir(&resolved) {
+        Ok(resolved)
+    } else {
+        Err(format!("Access denied: {} was not selected in a dialog or written by a conversion in this session", resolved.display()))
+    }
+}
+
+// Resolve a path and check it is a picked/converted file or sits directly in a picked folder
+pub fn check_scoped(path: &str) -> Result<PathBuf, String> {
+    resolve_scoped(path).map(|resolved| simplified(&resolved))
+}
+
+// Only files the user picked or conversions wrote may be opened, and never anything executable
+pub fn check_openable(path: &str) -> Result<PathBuf, String> {
+    let resolved = resolve(path)?;
+    if !is_allowed_file(&resolved) {
+        return Err(format!("Cannot open {}: only files picked in a dialog or written by a conversion in this session can be opened from the app", resolved.display()));
+    }
+
+    let metadata = fs::metadata(&resolved)
+        .map_err(|e| format!("Cannot open {}: {}", resolved.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("Not a file: {}", resolved.display()));
+    }
+
+    let extension = resolved.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if EXECUTABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!("Refusing to open executable file: {}", resolved.display()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return Err(format!("Refusing to open executable file: {}", resolved.display()));
+        }
+    }
+
+    Ok(simplified(&resolved))
+}
+
+// Modification times on some filesystems (FAT, SMB) are only accurate to a couple of seconds
+const MTIME_SLACK: Duration = Duration::from_secs(2);
+
+// Record the file a conversion just wrote, wherever the output folder is (typed, default or picked).
+// The path comes from the webview, so it must be the command's -o target and have been written
+// since the conversion started
+pub fn allow_output(path: &str, args: &[String], started: SystemTime) -> Result<(), String> {
+    let target = args.iter().enumerate().find_map(|(i, arg)| match arg.as_str() {
+        "-o" | "--output" => args.get(i + 1).map(String::as_str),
+        _ => arg.strip_prefix("--output="),
+    });
+    if target != Some(path) {
+        return Err(format!("{} is not the output of the pandoc command", path));
+    }
+
+    let resolved = resolve(path)?;
+    let modified = fs::metadata(&resolved)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("Conversion output {} was not written: {}", resolved.display(), e))?;
+    if modified + MTIME_SLACK < started {
+        return Err(format!("{} was not written by this conversion", resolved.display()));
+    }
+    if resolved.is_file() {
+        allow_file(&resolved);
+    }
+    Ok(())
+}
+
+// Record a file the app keeps in its own data folder (template library) so it can be opened for editing
+pub fn allow_app_file(path: &Path) {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        allow_file(&resolved);
+    }
+}
+
+// Record an opened book project: its folder, every chapter wherever below the root it sits, and the
+// folders chapters are in, since the output defaults to the first chapter's folder
+pub fn allow_project(root: &Path, chapters: &[PathBuf]) {
+    allow_dir(root);
+    for chapter in chapters {
+        allow_file(chapter);
+        if let Some(parent) = chapter.parent() {
+            allow_dir(parent);
+        }
+    }
+}
+
+// Input file picker; the input's folder becomes the default output folder, so it is allowed too
+#[tauri::command]
+pub async fn select_input_file(app: AppHandle, extensions: Vec<String>) -> Result<Option<String>, String> {
+    let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+    let picked = app.dialog()
+        .file()
+        .add_filter("Documents", &extensions)
+        .blocking_pick_file();
+
+    let Some(picked) = picked else { return Ok(None) };
+    let path = picked.into_path()
+        .map_err(|e| format!("Invalid file selection: {}", e))?;
+    let resolved = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+
+    allow_file(&resolved);
+    if let Some(parent) = resolved.parent() {
+        allow_dir(parent);
+    }
+    Ok(Some(simplified(&resolved).to_string_lossy().to_string()))
+}
+
+#[tauri::command]
+pub async fn select_output_dir(app: AppHandle) -> Result<Option<String>, String> {
+    let Some(picked) = app.dialog().file().blocking_pick_folder() else { return Ok(None) };
+    let path = picked.into_path()
+        .map_err(|e| format!("Invalid folder selection: {}", e))?;
+    let resolved = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+
+    allow_dir(&resolved);
+    Ok(Some(simplified(&resolved).to_string_lossy().to_string()))
+}
@@ -0,0 +1,552 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use log::info;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::community;
+use crate::deps::{detect, find_in_path};
+use crate::scope;
+use crate::{get_extended_path, slug, unique_id};
+
+// Library metadata lives next to the app's other data; the files themselves go in TEMPLATES_DIR
+const TEMPLATES_FILE: &str = "templates.json";
+const TEMPLATES_DIR: &str = "templates";
+
+// Id prefix of community templates, which live in pandoc's templates folder instead of the library
+const COMMUNITY_PREFIX: &str = "community:";
+
+// LaTeX and HTML templates are passed with --template, office documents with --reference-doc
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateKind {
+    Latex,
+    Html,
+    Docx,
+    Odt,
+    Pptx,
+}
+
+impl TemplateKind {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "tex" | "latex" => Some(TemplateKind::Latex),
+            "html" | "htm" => Some(TemplateKind::Html),
+            "docx" => Some(TemplateKind::Docx),
+            "odt" => Some(TemplateKind::Odt),
+            "pptx" => Some(TemplateKind::Pptx),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            TemplateKind::Latex => "latex",
+            TemplateKind::Html => "html",
+            TemplateKind::Docx => "docx",
+            TemplateKind::Odt => "odt",
+            TemplateKind::Pptx => "pptx",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TemplateKind::Latex => "LaTeX",
+            TemplateKind::Html => "HTML",
+            TemplateKind::Docx => "Word",
+            TemplateKind::Odt => "ODT",
+            TemplateKind::Pptx => "PowerPoint",
+        }
+    }
+
+    // Output formats the template applies to; every PDF engine the app offers goes through LaTeX
+    pub fn formats(self) -> &'static [&'static str] {
+        match self {
+            TemplateKind::Latex => &["pdf", "latex", "beamer"],
+            TemplateKind::Html => &["html", "html5"],
+            TemplateKind::Docx => &["docx"],
+            TemplateKind::Odt => &["odt"],
+            TemplateKind::Pptx => &["pptx"],
+        }
+    }
+
+    pub fn flag(self) -> &'static str {
+        match self {
+            TemplateKind::Latex | TemplateKind::Html => "--template",
+            TemplateKind::Docx | TemplateKind::Odt | TemplateKind::Pptx => "--reference-doc",
+        }
+    }
+
+    // Arguments that make pandoc print its built-in template or reference document
+    fn default_args(self) -> Vec<String> {
+        match self {
+            TemplateKind::Latex | TemplateKind::Html => vec!["-D".to_string(), self.extension().to_string()],
+            _ => vec!["--print-default-data-file".to_string(), format!("reference.{}", self.extension())],
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableKind {
+    Bool,
+    Text,
+    Number,
+    Color,
+}
+
+// A -V variable a template reads, offered as a field next to the template picker
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateVariable {
+    pub name: String,
+    pub kind: VariableKind,
+    pub description: String,
+    // What the template falls back to when the variable isn't set, shown as a placeholder
+    pub default: Option<String>,
+}
+
+// A template or reference document copied into the library; `file` is its name inside the library folder
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    pub kind: TemplateKind,
+    pub file: String,
+    // Where it came from: the imported file, or the pandoc version whose default it is
+    pub source: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateListing {
+    #[serde(flatten)]
+    pub template: Template,
+    pub path: String,
+    pub formats: &'static [&'static str],
+    // False when the file was deleted from the library folder by hand
+    pub available: bool,
+    // Installed into pandoc's templates folder from the community catalog rather than imported
+    pub community: bool,
+    pub variables: Vec<TemplateVariable>,
+}
+
+fn templates_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(TEMPLATES_FILE))
+        .map_err(|e| format!("Could not find app data directory: {}", e))
+}
+
+fn library_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(TEMPLATES_DIR))
+        .map_err(|e| format!("Could not find app data directory: {}", e))
+}
+
+fn load_user_templates(app: &AppHandle) -> Result<Vec<Template>, String> {
+    let path = templates_path(app)?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_user_templates(app: &AppHandle, templates: &[Template]) -> Result<(), String> {
+    let path = templates_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(templates)
+        .map_err(|e| format!("Failed to serialize templates: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn listing(dir: &Path, template: Template) -> TemplateListing {
+    let path = dir.join(&template.file);
+    let available = path.is_file();
+    if available {
+        scope::allow_app_file(&path);
+    }
+    TemplateListing {
+        path: path.to_string_lossy().to_string(),
+        formats: template.kind.formats(),
+        available,
+        community: false,
+        variables: vec![],
+        template,
+    }
+}
+
+// Community templates as picker entries, one per LaTeX/HTML file (Eisvogel's .beamer file has no
+// matching output format); ids are prefixed so they never collide with library ids
+fn community_listings(app: &AppHandle) -> Result<Vec<TemplateListing>, String> {
+    let dir = community::templates_dir()?;
+    let mut listings = Vec::new();
+    for installed in community::load_installed(app)? {
+        for file in &installed.files {
+            let extension = Path::new(file).extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+            let Some(kind) = TemplateKind::from_extension(&extension) else { continue };
+            let mut listing = listing(&dir, Template {
+                id: format!("{}{}", COMMUNITY_PREFIX, file),
+                name: format!("{} {}", installed.name, installed.version),
+                kind,
+                file: file.clone(),
+                source: installed.source.clone(),
+            });
+            listing.community = true;
+            listing.variables = community::variables(&installed.id);
+            listings.push(listing);
+        }
+    }
+    Ok(listings)
+}
+
+// Write a file into the library under a fresh id and record it
+fn add_to_library(app: &AppHandle, name: &str, kind: TemplateKind, content: &[u8], source: String) -> Result<TemplateListing, String> {
+    let dir = library_dir(app)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let mut templates = load_user_templates(app)?;
+    let id = unique_id(&slug(name, "template"), |id| templates.iter().any(|t| t.id == id));
+    let file = format!("{}.{}", id, kind.extension());
+    let path = dir.join(&file);
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let template = Template { id, name: name.to_string(), kind, file, source };
+    templates.push(template.clone());
+    save_user_templates(app, &templates)?;
+    info!("Added {} template '{}' to the library", kind.label(), template.name);
+    Ok(listing(&dir, template))
+}
+
+// A library or community template by id
+fn find_listing(app: &AppHandle, id: &str) -> Result<TemplateListing, String> {
+    if let Some(file) = id.strip_prefix(COMMUNITY_PREFIX) {
+        return community_listings(app)?
+            .into_iter()
+            .find(|l| l.template.id == id)
+            .ok_or_else(|| format!("Community template '{}' is not installed", file));
+    }
+    let template = load_user_templates(app)?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Unknown template '{}': it may have been deleted from the template library", id))?;
+    Ok(listing(&library_dir(app)?, template))
+}
+
+// The --template/--reference-doc flag and file for a library template, checked against the output format
+pub fn resolve(app: &AppHandle, id: &str, target: &str) -> Result<(&'static str, PathBuf), String> {
+    let listing = find_listing(app, id)?;
+    let template = &listing.template;
+    if !template.kind.formats().contains(&target) {
+        return Err(format!(
+            "'{}' is a {} template and can't be used for {} output",
+            template.name, template.kind.label(), target
+        ));
+    }
+    if !listing.available {
+        return Err(format!("Template '{}' not found at {}", template.name, listing.path));
+    }
+    Ok((template.kind.flag(), PathBuf::from(listing.path)))
+}
+
+// Variables pandoc fills in from the document and its options, or that the app already sets from its
+// own settings (fonts, paper, links, title page); templates use them but they get no field
+const AUTOMATIC_VARIABLES: &[&str] = &[
+    "body", "title", "subtitle", "author", "date", "abstract", "keywords", "subject", "lang", "dir",
+    "pagetitle", "title-meta", "author-meta", "date-meta", "sourcefile", "outputfile", "curdir",
+    "pandoc-version", "meta-json", "header-includes", "include-before", "include-after",
+    "toc", "toc-title", "toc-depth", "toc-own-page", "lof", "lot", "numbersections", "secnumdepth",
+    "highlighting-macros", "highlighting-css", "document-css", "css", "csl-css", "csl-refs", "csl-hanging-indent",
+    "csl-entry-spacing", "natbib", "biblatex", "bibliography", "biblio-style", "biblio-title",
+    "math", "mathml", "mathjax", "katex", "tables", "graphics", "svg", "strikeout", "verbatim-in-note",
+    "listings", "lhs", "beamer", "has-frontmatter", "has-chapters", "babel-lang", "babel-otherlangs",
+    "polyglossia-lang", "polyglossia-otherlangs", "bidi", "documentclass", "papersize", "geometry",
+    "mainfont", "monofont", "fontsize", "linestretch", "colorlinks", "linkcolor", "urlcolor",
+    "titlepage", "titlepage-rule-height",
+];
+
+// How a template uses a variable: printed ($name$, $for(name)$) or only tested ($if(name)$)
+#[derive(Default)]
+struct Usage {
+    printed: bool,
+    tested: bool,
+}
+
+fn is_variable_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// Variables (in order of first use) and partials of a pandoc template: $name$, ${name}, $if(name)$,
+// $for(name)$, $name.field$, $name/pipe$, $name:partial()$ and $partial()$
+fn scan_template(source: &str) -> (Vec<(String, Usage)>, Vec<String>) {
+    let mut variables: Vec<(String, Usage)> = Vec::new();
+    let mut partials = Vec::new();
+    let mut record = |name: &str, tested: bool| {
+        let name = name.trim();
+        let name = name.split(['.', '/']).next().unwrap_or_default();
+        if !is_variable_name(name) || name == "it" {
+            return;
+        }
+        let index = match variables.iter().position(|(existing, _)| existing == name) {
+            Some(index) => index,
+            None => {
+                variables.push((name.to_string(), Usage::default()));
+                variables.len() - 1
+            },
+        };
+        let usage = &mut variables[index].1;
+        if tested { usage.tested = true } else { usage.printed = true }
+    };
+
+    let mut rest = source;
+    while let Some(start) = rest.find('$') {
+        let after = &rest[start + 1..];
+        // $$ is a literal dollar sign and $-- starts a comment running to the end of the line
+        if let Some(literal) = after.strip_prefix('$') {
+            rest = literal;
+            continue;
+        }
+        if after.starts_with("--") {
+            rest = after.find('\n').map(|i| &after[i..]).unwrap_or_default();
+            continue;
+        }
+        let closing = if after.starts_with('{') { '}' } else { '$' };
+        let body = after.strip_prefix('{').unwrap_or(after);
+        let Some(end) = body.find(closing) else { break };
+        let inner = body[..end].trim();
+        rest = &body[end + 1..];
+
+        if let Some(name) = inner.strip_prefix("if(").or_else(|| inner.strip_prefix("elseif(")) {
+            record(name.trim_end_matches(')'), true);
+        } else if let Some(name) = inner.strip_prefix("for(") {
+            record(name.trim_end_matches(')'), false);
+        } else if let Some(call) = inner.find('(') {
+            // $partial()$ or $variable:partial()$, possibly followed by pipes or a [separator]
+            let head = &inner[..call];
+            let partial = match head.split_once(':') {
+                Some((variable, partial)) => {
+                    record(variable, false);
+                    partial
+                },
+                None => head,
+            };
+            let partial = partial.trim().to_string();
+            if !partial.is_empty() && !partials.contains(&partial) {
+                partials.push(partial);
+            }
+        } else if !matches!(inner, "else" | "endif" | "endfor" | "sep" | "^" | "~") {
+            record(inner, false);
+        }
+    }
+
+    (variables, partials)
+}
+
+// A partial as pandoc finds it: next to the template, in the user data templates folder, then built in.
+// Partials without an extension take the template's
+fn read_partial(dir: &Path, name: &str, extension: &str) -> Option<String> {
+    let file = if Path::new(name).extension().is_some() { name.to_string() } else { format!("{}.{}", name, extension) };
+    if file.contains(['/', '\\']) {
+        return None;
+    }
+    let user_dir = community::templates_dir().ok();
+    if let Some(content) = [Some(dir.to_path_buf()), user_dir].into_iter().flatten().find_map(|d| fs::read_to_string(d.join(&file)).ok()) {
+        return Some(content);
+    }
+
+    let pandoc = find_in_path("pandoc")?;
+    let output = Command::new(pandoc)
+        .args(["--print-default-data-file", &format!("templates/{}", file)])
+        .env("PATH", get_extended_path())
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Variables a template and its partials use that the user can set with -V; documented variables
+// (community catalog) come first with their descriptions, then the rest in order of use
+fn template_variables(path: &Path, documented: Vec<TemplateVariable>) -> Result<Vec<TemplateVariable>, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+
+    let (mut used, mut pending) = scan_template(&source);
+    let mut seen = Vec::new();
+    while let Some(partial) = pending.pop() {
+        if seen.contains(&partial) {
+            continue;
+        }
+        seen.push(partial.clone());
+        let Some(content) = read_partial(dir, &partial, &extension) else { continue };
+        let (variables, partials) = scan_template(&content);
+        for (name, usage) in variables {
+            match used.iter_mut().find(|(existing, _)| *existing == name) {
+                Some((_, existing)) => {
+                    existing.printed |= usage.printed;
+                    existing.tested |= usage.tested;
+                },
+                None => used.push((name, usage)),
+            }
+        }
+        pending.extend(partials);
+    }
+
+    let mut variables = documented;
+    for (name, usage) in used {
+        if AUTOMATIC_VARIABLES.contains(&name.as_str()) || variables.iter().any(|v| v.name == name) {
+            continue;
+        }
+        let flag = usage.tested && !usage.printed;
+        variables.push(TemplateVariable {
+            description: if flag {
+                format!("Switch the template tests with $if({})$", name)
+            } else {
+                "Value the template inserts".to_string()
+            },
+            kind: if flag { VariableKind::Bool } else { VariableKind::Text },
+            default: None,
+            name,
+        });
+    }
+    Ok(variables)
+}
+
+#[tauri::command]
+pub fn list_templates(app: AppHandle) -> Result<Vec<TemplateListing>, String> {
+    let dir = library_dir(&app)?;
+    let mut listings: Vec<TemplateListing> = load_user_templates(&app)?
+        .into_iter()
+        .map(|template| listing(&dir, template))
+        .collect();
+    listings.extend(community_listings(&app)?);
+    Ok(listings)
+}
+
+// Copy a picked template or reference document into the library, so presets keep working when
+// the original is moved; the kind comes from the file extension
+#[tauri::command]
+pub async fn import_template(app: AppHandle) -> Result<Option<TemplateListing>, String> {
+    let picked = app.dialog()
+        .file()
+        .add_filter("Templates and reference documents", &["latex", "tex", "html", "htm", "docx", "odt", "pptx"])
+        .blocking_pick_file();
+
+    let Some(picked) = picked else { return Ok(None) };
+    let path = picked.into_path()
+        .map_err(|e| format!("Invalid file selection: {}", e))?;
+    let extension = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    let kind = TemplateKind::from_extension(&extension)
+        .ok_or_else(|| format!("Unsupported template type: {}", path.display()))?;
+    let content = fs::read(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    add_to_library(&app, &name, kind, &content, path.to_string_lossy().to_string()).map(Some)
+}
+
+// Save pandoc's own template or reference document for a kind into the library as a starting point
+#[tauri::command]
+pub async fn export_default_template(app: AppHandle, kind: TemplateKind) -> Result<TemplateListing, String> {
+    let pandoc = find_in_path("pandoc")
+        .ok_or_else(|| "pandoc not found on PATH. Install it from Check Dependencies.".to_string())?;
+
+    tokio::task::spawn_blocking(move || {
+        let args = kind.default_args();
+        let output = Command::new(&pandoc)
+            .args(&args)
+            .env("PATH", get_extended_path())
+            .output()
+            .map_err(|e| format!("Failed to execute pandoc: {}", e))?;
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(format!("pandoc {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+        }
+
+        let version = detect("pandoc").unwrap_or_else(|_| "pandoc".to_string());
+        let name = format!("Default {} ({})", kind.label(), version);
+        add_to_library(&app, &name, kind, &output.stdout, version)
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn delete_template(app: AppHandle, id: String) -> Result<(), String> {
+    let mut templates = load_user_templates(&app)?;
+    let template = templates.iter()
+        .find(|t| t.id == id)
+        .cloned()
+        .ok_or_else(|| format!("No template named '{}' in the library", id))?;
+
+    let path = library_dir(&app)?.join(&template.file);
+    if path.exists() {
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    }
+    templates.retain(|t| t.id != id);
+    save_user_templates(&app, &templates)
+}
+
+// Parse a LaTeX or HTML template (and its partials) for the variables it reads, so the UI can offer
+// fields for them instead of guessing -V names
+#[tauri::command]
+pub async fn inspect_template_variables(app: AppHandle, id: String) -> Result<Vec<TemplateVariable>, String> {
+    let listing = find_listing(&app, &id)?;
+    if listing.template.kind.flag() != "--template" {
+        return Err(format!("{} reference documents have no template variables", listing.template.kind.label()));
+    }
+    tokio::task::spawn_blocking(move || template_variables(Path::new(&listing.path), listing.variables))
+        .await
+        .map_err(|e| format!("Task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage<'a>(variables: &'a [(String, Usage)], name: &str) -> &'a Usage {
+        &variables.iter().find(|(existing, _)| existing == name).unwrap().1
+    }
+
+    #[test]
+    fn variables_in_order_with_their_usage() {
+        let source = "$if(titlepage)$\n${titlepage-color}\n$endif$\n$for(author)$$author.name$$sep$, $endfor$\n$logo-width/uppercase$\n";
+        let (variables, partials) = scan_template(source);
+        let names: Vec<&str> = variables.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["titlepage", "titlepage-color", "author", "logo-width"]);
+        assert!(usage(&variables, "titlepage").tested && !usage(&variables, "titlepage").printed);
+        assert!(usage(&variables, "titlepage-color").printed);
+        assert!(usage(&variables, "author").printed);
+        assert!(partials.is_empty());
+    }
+
+    #[test]
+    fn tested_and_printed_variables() {
+        let (variables, _) = scan_template("$if(watermark)$\\watermark{$watermark$}\n$elseif(book)$\n$endif$");
+        assert!(usage(&variables, "watermark").tested && usage(&variables, "watermark").printed);
+        assert!(usage(&variables, "book").tested && !usage(&variables, "book").printed);
+    }
+
+    #[test]
+    fn partials_comments_and_literal_dollars() {
+        let source = "$-- $ignored$ in a comment\nCosts $$5\n$common()$\n${ styles.html() }\n$authors:author-line()[, ]$\n$for(x)$$it$$endfor$\n";
+        let (variables, partials) = scan_template(source);
+        let names: Vec<&str> = variables.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["authors", "x"]);
+        assert_eq!(partials, vec!["common", "styles.html", "author-line"]);
+    }
+}
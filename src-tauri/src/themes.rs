@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+// User themes live next to the app's other data as a JSON array
+const THEMES_FILE: &str = "themes.json";
+
+// Output theme colors, stored as 6-digit hex without the leading '#'
+// The same data renders to a LaTeX header (PDF) and CSS (HTML/EPUB)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputTheme {
+    pub id: String,
+    pub name: String,
+    pub page_background: String,
+    pub text: String,
+    pub link: String,
+    pub heading: String,
+    // Code colors are optional so a theme can leave the highlight style's own colors alone
+    #[serde(default)]
+    pub code_background: Option<String>,
+    #[serde(default)]
+    pub code_text: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeListing {
+    #[serde(flatten)]
+    pub theme: OutputTheme,
+    pub builtin: bool,
+    pub css: String,
+}
+
+fn theme(id: &str, name: &str, colors: [&str; 4], code: Option<(&str, &str)>) -> OutputTheme {
+    OutputTheme {
+        id: id.to_string(),
+        name: name.to_string(),
+        page_background: colors[0].to_string(),
+        text: colors[1].to_string(),
+        link: colors[2].to_string(),
+        heading: colors[3].to_string(),
+        code_background: code.map(|(bg, _)| bg.to_string()),
+        code_text: code.map(|(_, fg)| fg.to_string()),
+    }
+}
+
+pub fn builtin_themes() -> Vec<OutputTheme> {
+    vec![
+        // The original dark mode palette (Catppuccin Mocha)
+        theme("dark", "Dark (Catppuccin Mocha)", ["1e1e2e", "cdd6f4", "89b4fa", "cba6f7"], Some(("313244", "cdd6f4"))),
+        theme("latte", "Light (Catppuccin Latte)", ["eff1f5", "4c4f69", "1e66f5", "8839ef"], Some(("e6e9ef", "4c4f69"))),
+        theme("nord", "Nord", ["2e3440", "d8dee9", "88c0d0", "81a1c1"], Some(("3b4252", "e5e9f0"))),
+        theme("sepia", "Sepia", ["f4ecd8", "5b4636", "8a4b08", "704214"], None),
+    ]
+}
+
+fn themes_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(THEMES_FILE))
+        .map_err(|e| format!("Could not find app data directory: {}", e))
+}
+
+fn load_user_themes(app: &AppHandle) -> Result<Vec<OutputTheme>, String> {
+    let path = themes_path(app)?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn save_user_themes(app: &AppHandle, themes: &[OutputTheme]) -> Result<(), String> {
+    let path = themes_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_string_pretty(themes)
+        .map_err(|e| format!("Failed to serialize themes: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// Built-ins overlaid with user themes; a user theme with a built-in id replaces it
+pub fn all_themes(app: &AppHandle) -> Result<Vec<OutputTheme>, String> {
+    let mut themes = builtin_themes();
+    for user in load_user_themes(app)? {
+        match themes.iter_mut().find(|t| t.id == user.id) {
+            Some(existing) => *existing = user,
+            None => themes.push(user),
+        }
+    }
+    Ok(themes)
+}
+
+pub fn find_theme(app: &AppHandle, id: &str) -> Result<OutputTheme, String> {
+    all_themes(app)?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Unknown output theme: {}", id))
+}
+
+// Accept "#RRGGBB" or "RRGGBB" and store lowercase without '#'
+fn normalize_color(field: &str, value: &str) -> Result<String, String> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(hex.to_lowercase())
+    } else {
+        Err(format!("Invalid {} color '{}': expected #RRGGBB", field, value))
+    }
+}
+
+impl OutputTheme {
+    fn validated(mut self) -> Result<Self, String> {
+        if self.id.is_empty() || !self.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+            return Err(format!("Invalid theme id '{}': use lowercase letters, digits and '-'", self.id));
+        }
+        self.page_background = normalize_color("page background", &self.page_background)?;
+        self.text = normalize_color("text", &self.text)?;
+        self.link = normalize_color("link", &self.link)?;
+        self.heading = normalize_color("heading", &self.heading)?;
+        self.code_background = self.code_background.as_deref().map(|c| normalize_color("code background", c)).transpose()?;
+        self.code_text = self.code_text.as_deref().map(|c| normalize_color("code text", c)).transpose()?;
+        Ok(self)
+    }
+
+    // LaTeX header for PDF output
+    // Links are colored through \hypersetup at \begin{document} so it works whether the
+    // template loads hyperref before or after header-includes
+    pub fn to_latex(&self) -> String {
+        let mut out = format!(
+            r#"\usepackage{{pagecolor}}
+\usepackage{{xcolor}}
+\definecolor{{themebg}}{{HTML}}{{{}}}
+\definecolor{{themetext}}{{HTML}}{{{}}}
+\definecolor{{themelink}}{{HTML}}{{{}}}
+\definecolor{{themeheading}}{{HTML}}{{{}}}
+\pagecolor{{themebg}}
+\color{{themetext}}
+\AtBeginDocument{{\hypersetup{{colorlinks=true,linkcolor=themelink,urlcolor=themelink,filecolor=themelink,citecolor=themelink}}}}
+\makeatletter
+\ifdefined\addtokomafont
+  \addtokomafont{{disposition}}{{\color{{themeheading}}}}
+\else\@ifclassloaded{{memoir}}{{%
+  \renewcommand{{\chaptitlefont}}{{\normalfont\huge\bfseries\color{{themeheading}}}}%
+  \setsecheadstyle{{\normalfont\Large\bfseries\color{{themeheading}}}}%
+  \setsubsecheadstyle{{\normalfont\large\bfseries\color{{themeheading}}}}%
+}}{{%
+  \usepackage{{sectsty}}%
+  \allsectionsfont{{\color{{themeheading}}}}%
+}}\fi
+\makeatother
+"#,
+            self.page_background, self.text, self.link, self.heading
+        );
+
+        // shadecolor is the background pandoc's highlighting uses for code blocks; redefine it at
+        // \begin{document} so the highlighting macros can't override it
+        if let Some(bg) = &self.code_background {
+            out.push_str(&format!("\\AtBeginDocument{{\\definecolor{{shadecolor}}{{HTML}}{{{}}}}}\n", bg));
+        }
+        if let Some(fg) = &self.code_text {
+            out.push_str(&format!(
+                "\\definecolor{{themecode}}{{HTML}}{{{}}}\n\\AtBeginDocument{{\\ifdefined\\Highlighting\\RecustomVerbatimEnvironment{{Highlighting}}{{Verbatim}}{{commandchars=\\\\\\{{\\}},formatcom=\\color{{themecode}}}}\\fi}}\n",
+                fg
+            ));
+        }
+
+        out
+    }
+
+    // CSS for HTML/EPUB output
+    pub fn to_css(&self) -> String {
+        let mut out = format!(
+            "body{{background-color:#{};color:#{}}}a{{color:#{}}}h1,h2,h3,h4,h5,h6{{color:#{}}}",
+            self.page_background, self.text, self.link, self.heading
+        );
+        if self.code_background.is_some() || self.code_text.is_some() {
+            out.push_str("pre,code{");
+            if let Some(bg) = &self.code_background {
+                out.push_str(&format!("background-color:#{};", bg));
+            }
+            if let Some(fg) = &self.code_text {
+                out.push_str(&format!("color:#{};", fg));
+            }
+            out.push('}');
+        }
+        out
+    }
+}
+
+#[tauri::command]
+pub fn list_output_themes(app: AppHandle) -> Result<Vec<ThemeListing>, String> {
+    let builtin_ids: Vec<String> = builtin_themes().into_iter().map(|t| t.id).collect();
+    Ok(all_themes(&app)?
+        .into_iter()
+        .map(|theme| ThemeListing {
+            builtin: builtin_ids.contains(&theme.id),
+            css: theme.to_css(),
+            theme,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn save_output_theme(app: AppHandle, theme: OutputTheme) -> Result<OutputTheme, String> {
+    let theme = theme.validated()?;
+    let mut user = load_user_themes(&app)?;
+    match user.iter_mut().find(|t| t.id == theme.id) {
+        Some(existing) => *existing = theme.clone(),
+        None => user.push(theme.clone()),
+    }
+    save_user_themes(&app, &user)?;
+    Ok(theme)
+}
+
+// Deleting an override of a built-in theme restores the built-in colors
+#[tauri::command]
+pub fn delete_output_theme(app: AppHandle, id: String) -> Result<(), String> {
+    let mut user = load_user_themes(&app)?;
+    let before = user.len();
+    user.retain(|t| t.id != id);
+    if user.len() == before {
+        return Err(format!("No user theme named '{}' (built-in themes cannot be deleted)", id));
+    }
+    save_user_themes(&app, &user)
+}
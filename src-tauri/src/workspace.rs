@@ -0,0 +1,415 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, SystemTime};
+use log::{info, warn};
+use serde::Deserialize;
+
+// All job workspaces live under one parent so stale ones can be swept on startup
+const JOBS_DIR: &str = "pandoc-gui-jobs";
+
+// Workspaces older than this are left over from crashed runs
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+static NEXT_JOB_ID: AtomicU32 = AtomicU32::new(1);
+
+// Variables the app sets for jobs (PANDOC_GUI_JOB_DIR, diagram renderers) that presets may not replace
+const RESERVED_ENV_PREFIX: &str = "PANDOC_GUI_";
+
+// Directory pandoc runs in; relative image and bibliography paths resolve against it
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case", tag = "kind", content = "path")]
+pub enum WorkingDir {
+    // The input file's folder, so documents behave as they do on the command line
+    #[default]
+    Input,
+    Output,
+    // The private job workspace
+    Workspace,
+    Custom(String),
+}
+
+// A private temp directory for one conversion
+// Holds generated headers, configs, filter outputs and pandoc's own temp files,
+// and is removed when the job finishes (on drop)
+pub struct JobWorkspace {
+    dir: PathBuf,
+}
+
+impl JobWorkspace {
+    pub fn create() -> Result<Self, String> {
+        let root = env::temp_dir().join(JOBS_DIR);
+        let id = NEXT_JOB_ID.fetch_add(1, Ordering::SeqCst);
+        let dir = root.join(format!("job-{}-{}", std::process::id(), id));
+
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create job workspace {}: {}", dir.display(), e))?;
+        info!("Created job workspace: {}", dir.display());
+
+        Ok(JobWorkspace { dir })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    // Write a generated file into the workspace and return its path
+    pub fn write(&self, name: &str, content: &str) -> Result<PathBuf, String> {
+        let path = self.dir.join(name);
+        fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    // Resolve the preset's working directory choice for this job
+    pub fn working_dir(&self, choice: &WorkingDir, input: Option<&str>, output: Option<&str>) -> Result<PathBuf, String> {
+        let parent_of = |path: Option<&str>, what: &str| {
+            path.and_then(|p| Path::new(p).parent())
+                .filter(|dir| dir.is_dir())
+                .map(Path::to_path_buf)
+                .ok_or_else(|| format!("Cannot use the {} folder as working directory: no {} path", what, what))
+        };
+
+        match choice {
+            // Pandoc and the engines write nothing into the cwd (temp files go to the workspace, mermaid
+            // runs there too), so a read-only input folder is fine; without an input use the workspace
+            WorkingDir::Input => parent_of(input, "input").or_else(|e| {
+                warn!("{}; using the job workspace", e);
+                Ok(self.dir.clone())
+            }),
+            WorkingDir::Output => parent_of(output, "output"),
+            WorkingDir::Workspace => Ok(self.dir.clone()),
+            WorkingDir::Custom(dir) => {
+                let dir = Path::new(dir.trim());
+                if dir.is_absolute() && dir.is_dir() {
+                    Ok(dir.to_path_buf())
+                } else {
+                    Err(format!("Working directory must be an existing absolute folder: {}", dir.display()))
+                }
+            },
+        }
+    }
+
+    // Filters that read config from their cwd (mermaid-filter's .mermaid-config.json) still need to
+    // run in the workspace when pandoc doesn't; this wraps the program in a script that cd's there first
+    pub fn filter_wrapper(&self, program: &str) -> Result<PathBuf, String> {
+        if cfg!(target_os = "windows") {
+            self.write(
+                &format!("{}-wrapper.cmd", program),
+                &format!("@echo off\r\ncd /d \"%PANDOC_GUI_JOB_DIR%\"\r\n{} %*\r\n", program),
+            )
+        } else {
+            let path = self.write(
+                &format!("{}-wrapper.sh", program),
+                &format!("#!/bin/sh\ncd \"$PANDOC_GUI_JOB_DIR\" || exit 1\nexec {} \"$@\"\n", program),
+            )?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                    .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+            }
+            Ok(path)
+        }
+    }
+
+    // Point the child at the workspace: cwd, temp dirs and a variable filters can use
+    pub fn apply(&self, command: &mut Command, working_dir: &Path) {
+        command
+            .current_dir(working_dir)
+            .env("PANDOC_GUI_JOB_DIR", &self.dir);
+        if cfg!(target_os = "windows") {
+            command.env("TEMP", &self.dir).env("TMP", &self.dir);
+        } else {
+            command.env("TMPDIR", &self.dir);
+        }
+    }
+}
+
+impl Drop for JobWorkspace {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            warn!("Failed to remove job workspace {}: {}", self.dir.display(), e);
+        }
+    }
+}
+
+// Set a preset's environment variables (TEXINPUTS, SOURCE_DATE_EPOCH, MERMAID_FILTER_THEME...) on the
+// child; applied after the app's own defaults so presets can override them
+pub fn apply_user_env(command: &mut Command, vars: &BTreeMap<String, String>) -> Result<(), String> {
+    for (name, value) in vars {
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("Invalid environment variable name: '{}'", name));
+        }
+        if name.starts_with(RESERVED_ENV_PREFIX) {
+            return Err(format!("{} is set by the app and cannot be overridden", name));
+        }
+        if value.contains('\0') {
+            return Err(format!("Environment variable {} contains a NUL character", name));
+        }
+        command.env(name, value);
+    }
+    Ok(())
+}
+
+// Remove workspaces left behind by runs that crashed or were killed
+pub fn sweep_stale_workspaces() {
+    let root = env::temp_dir().join(JOBS_DIR);
+    let Ok(entries) = fs::read_dir(&root) else { return };
+
+    for entry in entries.flatten() {
+        let stale = entry.metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > STALE_AFTER);
+
+        if stale {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+// Quote a value for a shell command line; split_command reads the same quoting back as one argument
+pub fn shell_quote(value: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+// Characters that would start another command, a redirection or a substitution in a shell
+const SHELL_OPERATORS: &[char] = &[';', '|', '&', '<', '>', '`', '(', ')', '\n'];
+
+// Split a command line into arguments with the quoting the app and the command preview use:
+// '...' is literal, "..." takes \" \\ \$ \` escapes, and an unquoted backslash escapes the next
+// character. On Windows backslashes are literal (paths) and "" inside double quotes is a quote.
+// Nothing is expanded except a leading ~/; operators and $ outside quotes are rejected, since the
+// arguments go straight to the program without a shell
+pub fn split_command(command: &str, windows: bool) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated single quote in command".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') if windows && chars.peek() == Some(&'"') => {
+                            chars.next();
+                            current.push('"');
+                        }
+                        Some('"') => break,
+                        Some('\\') if !windows => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("Unterminated double quote in command".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated double quote in command".to_string()),
+                    }
+                }
+            }
+            '\\' if !windows => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some(c) => {
+                    in_word = true;
+                    current.push(c);
+                }
+                None => return Err("Command ends with a backslash".to_string()),
+            },
+            '~' if !in_word && matches!(chars.peek(), None | Some('/')) => {
+                let home = dirs::home_dir().ok_or("Could not find the home directory")?;
+                in_word = true;
+                current.push_str(&home.to_string_lossy());
+            }
+            c if SHELL_OPERATORS.contains(&c) || c == '$' => {
+                return Err(format!("Shell syntax is not supported in the pandoc command: {}", c.escape_default()));
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+// Options that make pandoc run a program or script. The command from the webview may only name
+// these filters and engines; Lua filters, the diagram filter and headers are added by the backend
+// after the command is parsed
+const RUNNABLE_OPTIONS: &[&str] = &["filter", "lua-filter", "pdf-engine", "pdf-engine-opt", "defaults"];
+const FILTER_PROGRAMS: &[&str] = &["pandoc-crossref", "mermaid-filter"];
+const PDF_ENGINES: &[&str] = &[
+    "pdflatex", "lualatex", "xelatex", "latexmk", "tectonic", "context", "wkhtmltopdf", "weasyprint",
+    "pagedjs-cli", "prince", "typst", "groff", "pdfroff",
+];
+
+// Short options without a value, which can be grouped in front of -F, -L or -d (-sF prog)
+const SHORT_FLAGS: &str = "sNipCRhv";
+
+// A runnable option in one argument (--filter=x, --lua x, -Fx, -sd x) and its value
+fn runnable_option<'a>(arg: &'a str, next: Option<&'a str>) -> Option<(&'static str, &'a str)> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, value) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, next),
+        };
+        // pandoc also accepts an unambiguous prefix of a long option (--lua-f, --pdf-e)
+        let option = RUNNABLE_OPTIONS.iter().find(|option| **option == name)
+            .or_else(|| RUNNABLE_OPTIONS.iter().find(|option| !name.is_empty() && option.starts_with(name)))?;
+        return Some((option, value.unwrap_or_default()));
+    }
+
+    let flags = arg.strip_prefix('-')?;
+    for (i, flag) in flags.char_indices() {
+        let option = match flag {
+            'F' => "filter",
+            'L' => "lua-filter",
+            'd' => "defaults",
+            flag if SHORT_FLAGS.contains(flag) => continue,
+            _ => return None,
+        };
+        let rest = &flags[i + 1..];
+        let value = if rest.is_empty() { next.unwrap_or_default() } else { rest };
+        return Some((option, value));
+    }
+    None
+}
+
+// Refuse filters, engines and defaults files the app didn't offer, so the command can't run
+// arbitrary programs
+fn check_runnable(args: &[String]) -> Result<(), String> {
+    for (i, arg) in args.iter().enumerate() {
+        let Some((option, value)) = runnable_option(arg, args.get(i + 1).map(String::as_str)) else { continue };
+        let allowed = match option {
+            "filter" => FILTER_PROGRAMS,
+            "pdf-engine" => PDF_ENGINES,
+            _ => &[],
+        };
+        if !allowed.contains(&value) {
+            return Err(match option {
+                "lua-filter" => "Lua filters can't be passed in the pandoc command; add them to the filter list instead".to_string(),
+                "filter" | "pdf-engine" => format!("--{} {} is not one of the filters or PDF engines the app can run", option, value),
+                _ => format!("--{} is not supported in the pandoc command, since it can run other programs", option),
+            });
+        }
+    }
+    Ok(())
+}
+
+// Arguments of a single pandoc invocation; anything else is refused
+pub fn pandoc_args(command: &str) -> Result<Vec<String>, String> {
+    let mut args = split_command(command, cfg!(target_os = "windows"))?;
+    if args.is_empty() || args[0] != "pandoc" {
+        return Err("Only pandoc commands can be run".to_string());
+    }
+    args.remove(0);
+    check_runnable(&args)?;
+    Ok(args)
+}
+
+// Whether an option is in the arguments, as `--flag value` or `--flag=value`
+pub fn has_option(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag || arg.strip_prefix(flag).is_some_and(|rest| rest.starts_with('=')))
+}
+
+// Remove `-F name` (or --filter) from the arguments; true if it was there
+pub fn take_filter(args: &mut Vec<String>, name: &str) -> bool {
+    let before = args.len();
+    let mut i = 0;
+    while i < args.len() {
+        let separate = matches!(args[i].as_str(), "-F" | "--filter") && args.get(i + 1).map(String::as_str) == Some(name);
+        if separate {
+            args.drain(i..i + 2);
+        } else if args[i] == format!("-F{}", name) || args[i] == format!("--filter={}", name) {
+            args.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    args.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_quoted_arguments() {
+        let args = split_command(r#"pandoc 'my file.md' -V 'title=It'"'"'s $5' -o "out \"1\".pdf""#, false).unwrap();
+        assert_eq!(args, vec!["pandoc", "my file.md", "-V", "title=It's $5", "-o", "out \"1\".pdf"]);
+    }
+
+    #[test]
+    fn windows_paths_keep_backslashes() {
+        let args = split_command(r#"pandoc C:\Users\me\in.md -o "C:\out dir\a ""b"".pdf""#, true).unwrap();
+        assert_eq!(args, vec!["pandoc", r"C:\Users\me\in.md", "-o", r#"C:\out dir\a "b".pdf"#]);
+    }
+
+    #[test]
+    fn refuses_shell_syntax() {
+        for command in ["pandoc a.md; rm -rf ~", "pandoc $(whoami).md", "pandoc a.md | sh", "pandoc `id`.md", "pandoc a.md > x"] {
+            assert!(split_command(command, false).is_err(), "{}", command);
+        }
+        assert!(split_command("pandoc 'unterminated", false).is_err());
+        assert_eq!(pandoc_args("sh -c 'pandoc a.md'").unwrap_err(), "Only pandoc commands can be run");
+        assert_eq!(pandoc_args("pandoc a.md -o 'a;b.pdf'").unwrap(), vec!["a.md", "-o", "a;b.pdf"]);
+    }
+
+    #[test]
+    fn refuses_programs_the_app_did_not_offer() {
+        for command in [
+            "pandoc a.md -F /tmp/evil", "pandoc a.md --filter=sh", "pandoc a.md -F./evil", "pandoc a.md -sF evil",
+            "pandoc a.md --lua-filter x.lua", "pandoc a.md -L x.lua", "pandoc a.md --lua=x.lua",
+            "pandoc a.md --pdf-engine=/bin/sh", "pandoc a.md --pdf-e /tmp/tex", "pandoc a.md --pdf-engine-opt=-shell-escape",
+            "pandoc a.md -d evil.yaml", "pandoc a.md --defaults=evil.yaml",
+        ] {
+            assert!(pandoc_args(command).is_err(), "{}", command);
+        }
+        let args = pandoc_args("pandoc a.md -s -F pandoc-crossref -F mermaid-filter --pdf-engine=xelatex -V title=-F -o a.pdf").unwrap();
+        assert_eq!(args.len(), 11);
+    }
+
+    #[test]
+    fn takes_the_mermaid_filter_out() {
+        let mut args: Vec<String> = ["-F", "mermaid-filter", "a.md", "--filter=mermaid-filter", "-F", "pandoc-crossref"]
+            .iter().map(|arg| arg.to_string()).collect();
+        assert!(take_filter(&mut args, "mermaid-filter"));
+        assert_eq!(args, vec!["a.md", "-F", "pandoc-crossref"]);
+        assert!(has_option(&args, "-F") && !has_option(&args, "--resource-path"));
+    }
+}
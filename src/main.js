@@ -7,13 +7,25 @@ let inputFileContent = null;
 let outputDirPath = null;
 let isTauri = false;
 
+// Reader the backend detected for the input, used when the input format is on auto
+let detectedInputFormat = null;
+
+// Book project (ordered chapters from the backend); null when a single file is open
+let bookProject = null;
+// Set when the project watcher starts a conversion, so the overwrite prompt is skipped
+let autoConverting = false;
+let projectChangePending = false;
+
 // Dependency availability state
 const installedDeps = {
   pandoc: false,
   tectonic: false,
   texlive: false,  // Consolidated: includes pdflatex, xelatex, lualatex
   'mermaid-filter': false,
-  'pandoc-crossref': false
+  'pandoc-crossref': false,
+  plantuml: false,
+  graphviz: false,
+  d2: false
 };
 
 // Track active event listener to prevent leaks
@@ -37,6 +49,22 @@ async function detectTauri() {
 // Font detection - populated from system fonts via queryLocalFonts() API
 let systemFonts = [];
 let monoFonts = [];
+// Per-family metadata (styles, monospace, scripts) from the backend catalog, keyed by family
+let fontCatalog = new Map();
+// Fonts from the project fonts folder next to the input (not installed system-wide)
+let projectFonts = { dir: null, families: [] };
+
+// Known monospace font family patterns, used when the backend can't report spacing
+const monoPatterns = [
+  /mono/i, /courier/i, /consolas/i, /menlo/i, /monaco/i,
+  /fira\s*code/i, /jetbrains/i, /source\s*code/i, /inconsolata/i,
+  /hack/i, /cascadia/i, /iosevka/i, /sf\s*mono/i, /dejavu.*mono/i,
+  /liberation.*mono/i, /ubuntu.*mono/i, /roboto.*mono/i, /ibm.*plex.*mono/i,
+  /pt\s*mono/i, /droid.*mono/i, /anonymous/i, /terminus/i
+];
+
+const isMono = (fontFamily) => monoPatterns.some(p => p.test(fontFamily));
+const isMonoFamily = (info) => (info.detailed ? info.monospace : isMono(info.family));
 
 // Code theme colors
 const themeColors = {
@@ -52,6 +80,13 @@ const themeColors = {
   'solarized-dark': { bg: '#002b36', kw: '#859900', fn: '#268bd2', st: '#2aa198', cm: '#586e75' },
 };
 
+// Output color themes (page/text/link/heading/code), loaded from the backend
+// The fallback matches the built-in "dark" theme for web mode
+let outputThemes = [{
+  id: 'dark', name: 'Dark (Catppuccin Mocha)', builtin: true,
+  css: 'body{background-color:#1e1e2e;color:#cdd6f4}a{color:#89b4fa}h1,h2,h3,h4,h5,h6{color:#cba6f7}pre,code{background-color:#313244;color:#cdd6f4;}'
+}];
+
 // DOM helper
 const $ = id => document.getElementById(id);
 
@@ -77,23 +112,39 @@ function initTheme() {
 
 // System Fonts - uses Local Font Access API to get actual installed fonts
 async function loadSystemFonts() {
-  const mainFontSelect = $('mainFont');
-  const monoFontSelect = $('monoFont');
-
-  // Known monospace font family patterns
-  const monoPatterns = [
-    /mono/i, /courier/i, /consolas/i, /menlo/i, /monaco/i,
-    /fira\s*code/i, /jetbrains/i, /source\s*code/i, /inconsolata/i,
-    /hack/i, /cascadia/i, /iosevka/i, /sf\s*mono/i, /dejavu.*mono/i,
-    /liberation.*mono/i, /ubuntu.*mono/i, /roboto.*mono/i, /ibm.*plex.*mono/i,
-    /pt\s*mono/i, /droid.*mono/i, /anonymous/i, /terminus/i
-  ];
+  try {
+    // Prefer the backend catalog: it knows real monospace spacing and available styles
+    if (isTauri) {
+      try {
+        const { invoke } = await import('@tauri-apps/api/core');
+        const catalog = await invoke('list_font_catalog');
+        if (catalog && catalog.length > 0) {
+          applyFontCatalog(catalog);
+          console.log(`Loaded ${systemFonts.length} fonts from catalog, ${monoFonts.length} monospace`);
+        }
 
-  const isMono = (fontFamily) => monoPatterns.some(p => p.test(fontFamily));
+        // The catalog comes from an on-disk cache; the backend rescans in the background and
+        // sends the new catalog if fonts were installed or removed since
+        const { listen } = await import('@tauri-apps/api/event');
+        listen('fonts-changed', (event) => {
+          const { added, removed, catalog } = event.payload;
+          applyFontCatalog(catalog);
+          populateFontSelects();
+          updateFontStyleWarning();
+          console.log(`Fonts changed: ${added.length} added, ${removed.length} removed`);
+          if (added.length > 0) {
+            showToast(`${added.length} new font${added.length === 1 ? '' : 's'} available`, 'info');
+          }
+        });
+      } catch (catalogErr) {
+        console.log('Font catalog not available:', catalogErr);
+      }
+    }
 
-  try {
-    // Try Local Font Access API (Chrome 103+, requires permission)
-    if ('queryLocalFonts' in window) {
+    if (systemFonts.length > 0) {
+      // Already loaded from the catalog
+    } else if ('queryLocalFonts' in window) {
+      // Try Local Font Access API (Chrome 103+, requires permission)
       const fonts = await window.queryLocalFonts();
       const fontFamilies = new Set();
       const monoFamilies = new Set();
@@ -133,7 +184,38 @@ async function loadSystemFonts() {
     }
   }
 
-  // Populate dropdowns
+  populateFontSelects();
+}
+
+// Fill the main/mono dropdowns from systemFonts/monoFonts, keeping the current selection
+function populateFontSelects() {
+  const mainFontSelect = $('mainFont');
+  const monoFontSelect = $('monoFont');
+  const selected = { main: mainFontSelect.value, mono: monoFontSelect.value };
+
+  // Keep the "System Default" option
+  [mainFontSelect, monoFontSelect].forEach(select => {
+    while (select.children.length > 1) {
+      select.lastElementChild.remove();
+    }
+  });
+
+  // Project fonts come first in their own group
+  const addProjectGroup = (select, families) => {
+    if (families.length === 0) return;
+    const group = document.createElement('optgroup');
+    group.label = 'Project fonts';
+    families.forEach(f => {
+      const opt = document.createElement('option');
+      opt.value = f.family;
+      opt.textContent = f.family;
+      group.appendChild(opt);
+    });
+    select.appendChild(group);
+  };
+  addProjectGroup(mainFontSelect, projectFonts.families);
+  addProjectGroup(monoFontSelect, projectFonts.families.filter(isMonoFamily));
+
   systemFonts.forEach(font => {
     const opt = document.createElement('option');
     opt.value = font;
@@ -158,6 +240,669 @@ async function loadSystemFonts() {
     opt.disabled = true;
     mainFontSelect.appendChild(opt);
   }
+
+  // A font that was uninstalled falls back to "System Default"
+  const available = (select, value) => [...select.options].some(opt => opt.value === value);
+  mainFontSelect.value = available(mainFontSelect, selected.main) ? selected.main : '';
+  monoFontSelect.value = available(monoFontSelect, selected.mono) ? selected.mono : '';
+}
+
+// Apply a font catalog from the backend (initial load or a background refresh)
+function applyFontCatalog(catalog) {
+  fontCatalog = new Map(catalog.map(f => [f.family, f]));
+  projectFonts.families.forEach(f => fontCatalog.set(f.family, f));
+  systemFonts = catalog.map(f => f.family).sort((a, b) => a.localeCompare(b));
+  monoFonts = catalog
+    .filter(isMonoFamily)
+    .map(f => f.family)
+    .sort((a, b) => a.localeCompare(b));
+}
+
+function isProjectFont(family) {
+  return projectFonts.families.some(f => f.family === family);
+}
+
+// Look for a fonts folder next to the input (or the configured folder) and offer its fonts
+async function loadProjectFonts() {
+  const previous = projectFonts.families;
+  projectFonts = { dir: null, families: [] };
+  if (isTauri && inputFilePath) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      projectFonts = await invoke('list_project_fonts', {
+        inputPath: inputFilePath,
+        fontsDir: $('projectFontsDir').value.trim() || null
+      });
+    } catch (e) {
+      console.log('Could not scan project fonts:', e);
+    }
+  }
+
+  previous.forEach(f => fontCatalog.delete(f.family));
+  projectFonts.families.forEach(f => fontCatalog.set(f.family, f));
+  $('projectFontsStatus').textContent = projectFonts.dir
+    ? `${projectFonts.families.length} font famil${projectFonts.families.length === 1 ? 'y' : 'ies'} from ${projectFonts.dir}`
+    : 'No fonts folder next to the input';
+  populateFontSelects();
+  updateFontStyleWarning();
+  updateCommandPreview();
+}
+
+// Output themes - listed from the backend so PDF and HTML share one palette
+async function loadOutputThemes() {
+  if (isTauri) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      const themes = await invoke('list_output_themes');
+      if (themes && themes.length > 0) {
+        outputThemes = themes;
+      }
+    } catch (e) {
+      console.error('Failed to load output themes:', e);
+    }
+  }
+
+  const select = $('outputTheme');
+  const current = select.value;
+  select.innerHTML = '';
+  outputThemes.forEach(theme => {
+    const opt = document.createElement('option');
+    opt.value = theme.id;
+    opt.textContent = theme.name;
+    select.appendChild(opt);
+  });
+  if (outputThemes.some(t => t.id === current)) {
+    select.value = current;
+  }
+}
+
+// Engine and fonts the backend needs to build the Unicode fallback header
+function getUnicodeHeaderOptions() {
+  return {
+    engine: $('pdfEngine').value,
+    mainFont: $('mainFont').value || null,
+    monoFont: $('monoFont').value || null
+  };
+}
+
+// Show which glyph ranges the installed fallback fonts can't cover for the current engine
+async function updateUnicodeCoverage() {
+  const status = $('unicodeCoverage');
+  if (!isTauri || !$('unicodeFallback').checked) {
+    status.textContent = 'Box drawing, symbols, emoji and CJK from installed fallback fonts (PDF)';
+    return;
+  }
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const header = await invoke('preview_unicode_header', { options: getUnicodeHeaderOptions() });
+    const fallbacks = header.fallbackFonts.length > 0 ? header.fallbackFonts.join(', ') : 'none';
+    const uncovered = header.uncovered.map(r => r.name).join(', ');
+    status.textContent = `${header.variant}: fallbacks ${fallbacks}${uncovered ? ` · no coverage for ${uncovered}` : ''}`;
+  } catch (e) {
+    status.textContent = `Could not check fallback fonts: ${e}`;
+  }
+}
+
+// Check the input's characters against the selected fonts so missing glyphs aren't silently dropped
+async function updateGlyphCoverage() {
+  const status = $('glyphCoverage');
+  if (!isTauri || !inputFilePath) {
+    status.classList.add('hidden');
+    return;
+  }
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const report = await invoke('scan_font_coverage', {
+      path: inputFilePath,
+      mainFont: $('mainFont').value || null,
+      monoFont: $('monoFont').value || null
+    });
+    const describe = (label, coverage) => {
+      if (coverage.missing.length === 0) return null;
+      const chars = coverage.missing.slice(0, 8).map(g => g.character).join(' ');
+      const candidates = [...new Set(coverage.missing.flatMap(g => g.candidates))].slice(0, 3);
+      return `${label} (${coverage.resolved}) lacks ${coverage.missing.length} character(s): ${chars}` +
+        (candidates.length > 0 ? ` — try ${candidates.join(', ')}` : '');
+    };
+    const messages = [describe('Body font', report.body), describe('Code font', report.code)].filter(Boolean);
+    status.textContent = messages.join(' · ');
+    status.classList.toggle('hidden', messages.length === 0);
+  } catch (e) {
+    // Binary inputs (DOCX, EPUB...) and systems without fontconfig can't be scanned
+    console.log('Font coverage scan unavailable:', e);
+    status.classList.add('hidden');
+  }
+}
+
+// Selected fonts that lack bold/italic faces; fontspec fakes or drops those styles with warnings
+function getMissingFontStyles() {
+  const problems = [];
+  [['Main font', 'mainFont'], ['Monospace font', 'monoFont']].forEach(([label, id]) => {
+    const info = fontCatalog.get($(id).value);
+    if (info && info.detailed && info.missingStyles.length > 0) {
+      problems.push(`${label} "${info.family}" has no ${info.missingStyles.join(', ')} style`);
+    }
+  });
+  return problems;
+}
+
+function updateFontStyleWarning() {
+  const status = $('fontStyleWarning');
+  const problems = getMissingFontStyles();
+  status.textContent = problems.join(' · ');
+  status.classList.toggle('hidden', problems.length === 0);
+}
+
+// Preset environment variables from the KEY=value textarea ('#' starts a comment line)
+function getJobEnvironment() {
+  const env = {};
+  $('envVars').value.split('\n').forEach(line => {
+    const trimmed = line.trim();
+    if (!trimmed || trimmed.startsWith('#')) return;
+    const eq = trimmed.indexOf('=');
+    if (eq <= 0) {
+      throw new Error(`Invalid environment variable line: "${trimmed}" (expected KEY=value)`);
+    }
+    env[trimmed.slice(0, eq).trim()] = trimmed.slice(eq + 1).trim();
+  });
+  return env;
+}
+
+function getWorkingDir() {
+  const kind = $('workingDir').value;
+  return kind === 'custom'
+    ? { kind, path: $('workingDirCustom').value.trim() }
+    : { kind };
+}
+
+// Lua filters - built-in and registered filters listed by the backend; the preset stores the
+// enabled ids, in run order, in the hidden #luaFilters field
+let luaFilterCatalog = [];
+
+function getEnabledLuaFilters() {
+  return $('luaFilters').value.split(',').map(id => id.trim()).filter(Boolean);
+}
+
+function setEnabledLuaFilters(ids) {
+  $('luaFilters').value = ids.join(',');
+  renderLuaFilters();
+  updateCommandPreview();
+}
+
+async function loadLuaFilters() {
+  if (!isTauri) {
+    $('addLuaFilterBtn').disabled = true;
+    return;
+  }
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    luaFilterCatalog = await invoke('list_lua_filters');
+  } catch (e) {
+    console.error('Failed to list Lua filters:', e);
+    luaFilterCatalog = [];
+  }
+  renderLuaFilters();
+}
+
+function luaFilterButton(label, title, onClick, disabled = false) {
+  const button = document.createElement('button');
+  button.type = 'button';
+  button.className = 'btn btn-xs btn-ghost px-1';
+  button.textContent = label;
+  button.title = title;
+  button.disabled = disabled;
+  button.addEventListener('click', onClick);
+  return button;
+}
+
+// Enabled filters first in run order, then the rest
+function renderLuaFilters() {
+  const list = $('luaFilterList');
+  const enabled = getEnabledLuaFilters();
+  const byId = new Map(luaFilterCatalog.map(f => [f.id, f]));
+  const ordered = [
+    ...enabled.map(id => byId.get(id) || { id, name: id, description: 'Not found - remove or re-register it', builtin: false, available: false }),
+    ...luaFilterCatalog.filter(f => !enabled.includes(f.id))
+  ];
+
+  list.replaceChildren(...ordered.map(filter => {
+    const index = enabled.indexOf(filter.id);
+    const row = document.createElement('div');
+    row.className = `flex items-center gap-2 bg-base-200 rounded-lg px-2 py-1${filter.available ? '' : ' opacity-60'}`;
+
+    const checkbox = document.createElement('input');
+    checkbox.type = 'checkbox';
+    checkbox.className = 'checkbox checkbox-primary checkbox-xs';
+    checkbox.checked = index >= 0;
+    checkbox.addEventListener('change', () => {
+      setEnabledLuaFilters(checkbox.checked ? [...enabled, filter.id] : enabled.filter(id => id !== filter.id));
+    });
+
+    const text = document.createElement('div');
+    text.className = 'flex-1 min-w-0';
+    const name = document.createElement('span');
+    name.className = 'text-sm font-medium';
+    name.textContent = filter.name;
+    const desc = document.createElement('p');
+    desc.className = 'text-xs text-base-content/60 truncate';
+    desc.textContent = filter.builtin ? filter.description : (filter.path || filter.description);
+    desc.title = filter.description;
+    text.append(name, desc);
+    row.append(checkbox, text);
+
+    if (index >= 0) {
+      const move = (offset) => {
+        const ids = [...enabled];
+        [ids[index], ids[index + offset]] = [ids[index + offset], ids[index]];
+        setEnabledLuaFilters(ids);
+      };
+      row.append(
+        luaFilterButton('↑', 'Run earlier', () => move(-1), index === 0),
+        luaFilterButton('↓', 'Run later', () => move(1), index === enabled.length - 1)
+      );
+    }
+    if (!filter.builtin) {
+      row.append(luaFilterButton('✕', 'Remove from the filter list', () => removeLuaFilter(filter.id)));
+    }
+    return row;
+  }));
+}
+
+async function addLuaFilter() {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const filter = await invoke('add_lua_filter');
+    if (!filter) return;
+    await loadLuaFilters();
+    if (!getEnabledLuaFilters().includes(filter.id)) {
+      setEnabledLuaFilters([...getEnabledLuaFilters(), filter.id]);
+    }
+    showToast(`Filter "${filter.name}" added`, 'success');
+  } catch (e) {
+    showToast(`Failed to add filter: ${e}`, 'error');
+  }
+}
+
+async function removeLuaFilter(id) {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    if (luaFilterCatalog.some(f => f.id === id)) {
+      await invoke('remove_lua_filter', { id });
+    }
+  } catch (e) {
+    showToast(`Failed to remove filter: ${e}`, 'error');
+    return;
+  }
+  $('luaFilters').value = getEnabledLuaFilters().filter(f => f !== id).join(',');
+  await loadLuaFilters();
+  updateCommandPreview();
+}
+
+// Templates - the backend keeps imported templates and reference documents in the app's template
+// library; the preset stores one id per template kind in the hidden #templateIds field
+let templateCatalog = [];
+
+// Variables the backend parsed out of templates, by template id; cleared when the window regains
+// focus so edits made in an external editor show up
+const templateVariableCache = new Map();
+
+// Template kind each output format takes (PDF is always built through LaTeX)
+const templateKinds = { pdf: 'latex', latex: 'latex', html: 'html', docx: 'docx', odt: 'odt', pptx: 'pptx' };
+
+function getPresetTemplateIds() {
+  return $('templateIds').value.split(',').map(id => id.trim()).filter(Boolean);
+}
+
+// Library template the preset uses for the current output format, if any
+function getSelectedTemplate() {
+  const format = $('outputFormat').value;
+  return getPresetTemplateIds()
+    .map(id => templateCatalog.find(t => t.id === id))
+    .find(t => t && t.formats.includes(format)) || null;
+}
+
+// Replace the preset's template for one kind; an empty id goes back to pandoc's default
+function setPresetTemplate(kind, id) {
+  const others = getPresetTemplateIds().filter(existing => {
+    const template = templateCatalog.find(t => t.id === existing);
+    return !template || template.kind !== kind;
+  });
+  $('templateIds').value = (id ? [...others, id] : others).join(',');
+  renderTemplates();
+  updateCommandPreview();
+}
+
+async function loadTemplates() {
+  templateVariableCache.clear();
+  if (isTauri) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      templateCatalog = await invoke('list_templates');
+    } catch (e) {
+      console.error('Failed to list templates:', e);
+      templateCatalog = [];
+    }
+  }
+  renderTemplates();
+}
+
+function renderTemplates() {
+  const format = $('outputFormat').value;
+  const kind = templateKinds[format];
+  const selected = getSelectedTemplate();
+  const select = $('templateSelect');
+
+  const fallback = document.createElement('option');
+  fallback.value = '';
+  fallback.textContent = kind ? 'Pandoc default' : `No templates for ${format} output`;
+  const options = templateCatalog.filter(t => t.formats.includes(format)).map(template => {
+    const opt = document.createElement('option');
+    opt.value = template.id;
+    opt.textContent = template.available ? template.name : `${template.name} (file missing)`;
+    return opt;
+  });
+  select.replaceChildren(fallback, ...options);
+  select.value = selected ? selected.id : '';
+  select.disabled = !kind;
+
+  $('importTemplateBtn').disabled = !isTauri;
+  $('exportDefaultTemplateBtn').disabled = !isTauri || !kind;
+  $('openTemplateBtn').disabled = !selected || !selected.available;
+  // Community templates are removed from Check Dependencies, which installed them
+  $('deleteTemplateBtn').disabled = !selected || selected.community;
+
+  // Presets can outlive the templates they name
+  const missing = isTauri ? getPresetTemplateIds().filter(id => !templateCatalog.some(t => t.id === id)) : [];
+  const status = $('templateStatus');
+  if (missing.length > 0) {
+    status.textContent = `Not in the template library: ${missing.join(', ')}`;
+  } else if (selected) {
+    status.textContent = `${['latex', 'html'].includes(selected.kind) ? '--template' : '--reference-doc'} from ${selected.source}`;
+  } else {
+    status.textContent = '';
+  }
+  status.classList.toggle('hidden', !status.textContent);
+
+  renderTemplateVariables(selected);
+  if (isTauri && selected && selected.available && ['latex', 'html'].includes(selected.kind) && !templateVariableCache.has(selected.id)) {
+    inspectTemplateVariables(selected);
+  }
+}
+
+// Documented variables (community catalog) until the template itself has been parsed
+function getTemplateVariableList(template) {
+  return templateVariableCache.get(template.id) || template.variables;
+}
+
+async function inspectTemplateVariables(template) {
+  // Mark it as in flight so re-renders don't parse it again
+  templateVariableCache.set(template.id, template.variables);
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    templateVariableCache.set(template.id, await invoke('inspect_template_variables', { id: template.id }));
+  } catch (e) {
+    console.error('Failed to inspect template variables:', e);
+  }
+  if (getSelectedTemplate()?.id === template.id) {
+    renderTemplateVariables(template);
+    updateCommandPreview();
+  }
+}
+
+// Values of template variables in the preset, by name; only those the selected template declares are used
+function getTemplateVariableValues() {
+  try {
+    return JSON.parse($('templateVars').value || '{}');
+  } catch {
+    return {};
+  }
+}
+
+function setTemplateVariable(name, value) {
+  const values = getTemplateVariableValues();
+  if (value === '' || value === false) {
+    delete values[name];
+  } else {
+    values[name] = value;
+  }
+  $('templateVars').value = Object.keys(values).length > 0 ? JSON.stringify(values) : '';
+  updateCommandPreview();
+}
+
+// Set variables of the selected template, passed as -V name=value
+function getTemplateVariables() {
+  const template = getSelectedTemplate();
+  const values = getTemplateVariableValues();
+  const variables = {};
+  (template ? getTemplateVariableList(template) : []).forEach(variable => {
+    if (values[variable.name] !== undefined) {
+      variables[variable.name] = values[variable.name];
+    }
+  });
+  return variables;
+}
+
+// A field per variable the template documents: checkboxes for flags, text inputs (with a color
+// picker for colors) for the rest, showing the template's default as placeholder
+function renderTemplateVariables(template) {
+  const container = $('templateVariables');
+  const variables = template ? getTemplateVariableList(template) : [];
+  const values = getTemplateVariableValues();
+
+  container.replaceChildren(...variables.map(variable => {
+    const field = document.createElement('label');
+    field.className = 'form-control';
+    field.title = `${variable.description} (-V ${variable.name})`;
+    const value = values[variable.name];
+
+    if (variable.kind === 'bool') {
+      field.className = 'label cursor-pointer justify-start gap-2 py-0.5';
+      const checkbox = document.createElement('input');
+      checkbox.type = 'checkbox';
+      checkbox.className = 'checkbox checkbox-primary checkbox-xs';
+      checkbox.checked = value === true;
+      checkbox.addEventListener('change', () => setTemplateVariable(variable.name, checkbox.checked));
+      const text = document.createElement('span');
+      text.className = 'label-text text-xs';
+      text.textContent = variable.name;
+      field.append(checkbox, text);
+      return field;
+    }
+
+    const label = document.createElement('span');
+    label.className = 'label-text text-xs py-0.5';
+    label.textContent = variable.name;
+    const row = document.createElement('div');
+    row.className = 'flex gap-1';
+    const input = document.createElement('input');
+    input.type = 'text';
+    input.className = 'input input-bordered input-xs flex-1 min-w-0 font-mono';
+    input.placeholder = variable.default || '';
+    input.value = value || '';
+    input.addEventListener('input', () => setTemplateVariable(variable.name, input.value.trim()));
+    row.append(input);
+
+    // Colors are written as 6-digit hex without '#', like the link color
+    if (variable.kind === 'color') {
+      const picker = document.createElement('input');
+      picker.type = 'color';
+      picker.className = 'w-6 h-6 rounded cursor-pointer border border-base-300';
+      picker.value = `#${/^[0-9a-fA-F]{6}$/.test(input.value || input.placeholder) ? (input.value || input.placeholder) : 'ffffff'}`;
+      picker.addEventListener('input', () => {
+        input.value = picker.value.replace('#', '').toUpperCase();
+        setTemplateVariable(variable.name, input.value);
+      });
+      row.append(picker);
+    }
+    field.append(label, row);
+    return field;
+  }));
+  container.classList.toggle('hidden', variables.length === 0);
+}
+
+async function importTemplate() {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const template = await invoke('import_template');
+    if (!template) return;
+    await loadTemplates();
+    if (template.formats.includes($('outputFormat').value)) {
+      setPresetTemplate(template.kind, template.id);
+    }
+    showToast(`Template "${template.name}" imported`, 'success');
+  } catch (e) {
+    showToast(`Failed to import template: ${e}`, 'error');
+  }
+}
+
+// Copy pandoc's own template or reference document for the current format into the library to edit
+async function exportDefaultTemplate() {
+  const kind = templateKinds[$('outputFormat').value];
+  if (!kind) return;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const template = await invoke('export_default_template', { kind });
+    await loadTemplates();
+    setPresetTemplate(template.kind, template.id);
+    showToast(`Saved "${template.name}" to the template library`, 'success');
+  } catch (e) {
+    showToast(`Failed to copy the default template: ${e}`, 'error');
+  }
+}
+
+async function openTemplate() {
+  const template = getSelectedTemplate();
+  if (!template) return;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('open_file', { path: template.path });
+  } catch (e) {
+    showToast(`Could not open template: ${e}`, 'error');
+  }
+}
+
+async function deleteTemplate() {
+  const template = getSelectedTemplate();
+  if (!template || !confirm(`Delete "${template.name}" from the template library? Presets using it will fall back to pandoc's default.`)) {
+    return;
+  }
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('delete_template', { id: template.id });
+  } catch (e) {
+    showToast(`Failed to delete template: ${e}`, 'error');
+    return;
+  }
+  $('templateIds').value = getPresetTemplateIds().filter(id => id !== template.id).join(',');
+  await loadTemplates();
+  updateCommandPreview();
+}
+
+// Citation settings for the backend, which validates the bibliographies and adds --bibliography,
+// --csl and -M lang; null when nothing is configured
+function getCitationOptions() {
+  const bibliographies = $('bibliographies').value.split(';').map(p => p.trim()).filter(Boolean);
+  const style = $('cslStyle').value === 'custom' ? $('cslCustom').value.trim() : $('cslStyle').value;
+  const locale = $('citationLocale').value.trim();
+  if (!bibliographies.length && style === 'chicago' && !locale) {
+    return null;
+  }
+  return { bibliographies, style: style || null, locale: locale || null };
+}
+
+// Undefined, unused and duplicate keys from the backend's citation check, shown under the
+// bibliography field; returns the report (null when there is nothing to check)
+async function updateCitationCheck() {
+  const status = $('citationStatus');
+  const citations = getCitationOptions();
+  if (!isTauri || !inputFilePath || !citations || citations.bibliographies.length === 0) {
+    status.classList.add('hidden');
+    return null;
+  }
+
+  let report;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    report = await invoke('check_citations', {
+      inputPath: inputFilePath,
+      citations,
+      chapters: bookProject ? bookProject.paths : null
+    });
+  } catch (e) {
+    status.textContent = String(e);
+    status.className = 'text-xs text-error';
+    return null;
+  }
+
+  const parts = [`${report.cited.length} cited, ${report.defined} in bibliography`];
+  if (report.undefined.length) parts.push(`undefined: ${report.undefined.join(', ')}`);
+  if (report.duplicates.length) parts.push(`duplicate: ${report.duplicates.map(d => d.key).join(', ')}`);
+  if (report.unused.length) parts.push(`${report.unused.length} unused`);
+  status.textContent = parts.join(' · ');
+  status.title = report.unused.length ? `Unused: ${report.unused.join(', ')}` : '';
+  status.className = `text-xs ${report.undefined.length ? 'text-error' : (report.duplicates.length ? 'text-warning' : 'text-base-content/60')}`;
+  return report;
+}
+
+function updateCslCustomState() {
+  const custom = $('cslStyle').value === 'custom';
+  $('cslCustom').disabled = !custom;
+  $('browseCslBtn').disabled = !custom || !isTauri;
+}
+
+// Picked through the backend so the files enter the path scope
+async function pickCitationFile(extensions) {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke('select_input_file', { extensions });
+  } catch (e) {
+    showToast('Failed to open file dialog: ' + e, 'error');
+    return null;
+  }
+}
+
+function setupCitations() {
+  $('cslStyle').addEventListener('change', updateCslCustomState);
+  $('bibliographies').addEventListener('change', updateCitationCheck);
+  $('addBibliographyBtn').disabled = !isTauri;
+  $('addBibliographyBtn').addEventListener('click', async () => {
+    const path = await pickCitationFile(['bib', 'bibtex', 'json', 'yaml', 'yml']);
+    if (!path) return;
+    const current = $('bibliographies').value.split(';').map(p => p.trim()).filter(Boolean);
+    if (!current.includes(path)) {
+      $('bibliographies').value = [...current, path].join('; ');
+      updateCitationCheck();
+    }
+  });
+  $('browseCslBtn').addEventListener('click', async () => {
+    const path = await pickCitationFile(['csl']);
+    if (path) {
+      $('cslCustom').value = path;
+    }
+  });
+  updateCslCustomState();
+}
+
+// Mermaid rendering settings for the backend; empty fields keep mermaid-filter's defaults
+function getMermaidOptions() {
+  const number = (id) => {
+    const value = parseFloat($(id).value);
+    return Number.isFinite(value) ? value : null;
+  };
+  const width = number('mermaidWidth');
+  return {
+    theme: $('mermaidTheme').value,
+    format: $('mermaidFormat').value,
+    width: width !== null ? Math.round(width) : null,
+    scale: number('mermaidScale'),
+    background: $('mermaidBackground').value.trim() || null,
+    fontFamily: $('mermaidFont').value.trim() || null,
+    css: $('mermaidCss').value.trim() || null
+  };
+}
+
+function getSelectedOutputTheme() {
+  return outputThemes.find(t => t.id === $('outputTheme').value) || outputThemes[0];
 }
 
 // File Handling
@@ -170,13 +915,10 @@ async function setupFileHandling() {
     if (isTauri) {
       // Use Tauri dialog for file selection
       try {
-        const { open } = await import('@tauri-apps/plugin-dialog');
-        const selected = await open({
-          multiple: false,
-          filters: [{
-            name: 'Documents',
-            extensions: ['md', 'markdown', 'rst', 'tex', 'latex', 'docx', 'doc', 'html', 'htm', 'org', 'txt', 'adoc', 'asciidoc', 'epub', 'odt', 'rtf', 'json', 'yaml', 'yml']
-          }]
+        // The backend shows the dialog so the picked file (and its folder) enter the path scope
+        const { invoke } = await import('@tauri-apps/api/core');
+        const selected = await invoke('select_input_file', {
+          extensions: ['md', 'markdown', 'rst', 'tex', 'latex', 'docx', 'doc', 'html', 'htm', 'org', 'txt', 'adoc', 'asciidoc', 'epub', 'odt', 'rtf', 'json', 'yaml', 'yml']
         });
         if (selected) {
           await handleTauriFileSelect(selected);
@@ -200,11 +942,8 @@ async function setupFileHandling() {
     if (isTauri) {
       // Use Tauri dialog for folder selection
       try {
-        const { open } = await import('@tauri-apps/plugin-dialog');
-        const selected = await open({
-          directory: true,
-          multiple: false
-        });
+        const { invoke } = await import('@tauri-apps/api/core');
+        const selected = await invoke('select_output_dir');
         if (selected) {
           outputDirPath = selected.endsWith('/') ? selected : selected + '/';
           updateOutputDisplay();
@@ -232,6 +971,9 @@ async function setupFileHandling() {
 
 // Handle file selection in Tauri
 async function handleTauriFileSelect(filePath) {
+  if (bookProject) {
+    leaveBookProject();
+  }
   inputFilePath = filePath;
   inputFileName = filePath.split('/').pop();
 
@@ -255,10 +997,14 @@ async function handleTauriFileSelect(filePath) {
     const { readTextFile } = await import('@tauri-apps/plugin-fs');
     inputFileContent = await readTextFile(filePath);
     detectMermaid(inputFileContent);
+    updateGlyphCoverage();
+    loadProjectFonts();
+    updateCitationCheck();
   } catch (e) {
     console.error('Failed to read file:', e);
     inputFileContent = null;
   }
+  detectInputFormat();
 
   // Enable convert button
   $('convertBtn').disabled = false;
@@ -266,6 +1012,265 @@ async function handleTauriFileSelect(filePath) {
   updateCommandPreview();
 }
 
+// Input format - the backend detects the reader from the extension, front matter or content so
+// the command always passes -f. Markdown readers take the extension toggles they support; pandoc
+// refuses the others (gfm-smart is an error), so those are left out of -f
+const markdownReaders = {
+  markdown: ['hard_line_breaks', 'smart', 'emoji'],
+  markdown_strict: ['hard_line_breaks', 'smart', 'emoji'],
+  markdown_phpextra: ['hard_line_breaks', 'smart', 'emoji'],
+  markdown_mmd: ['hard_line_breaks', 'smart', 'emoji'],
+  markdown_github: ['hard_line_breaks', 'smart', 'emoji'],
+  commonmark: ['hard_line_breaks', 'smart', 'emoji'],
+  commonmark_x: ['hard_line_breaks', 'smart', 'emoji'],
+  gfm: ['hard_line_breaks', 'emoji']
+};
+const markdownExtensionToggles = {
+  mdHardLineBreaks: '+hard_line_breaks',
+  mdNoSmart: '-smart',
+  mdEmoji: '+emoji'
+};
+
+async function detectInputFormat() {
+  detectedInputFormat = null;
+  if (isTauri && inputFilePath) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      detectedInputFormat = await invoke('detect_input_format', { path: inputFilePath });
+    } catch (e) {
+      console.log('Could not detect input format:', e);
+    }
+  }
+  updateInputFormatState();
+  updateCommandPreview();
+}
+
+function getReader() {
+  const selected = $('fromFormat').value;
+  if (selected !== 'auto') return selected;
+  return detectedInputFormat ? detectedInputFormat.format : null;
+}
+
+// Whether a reader takes a toggle's extension ('+hard_line_breaks' -> hard_line_breaks)
+function readerSupports(reader, extension) {
+  return (markdownReaders[reader] || []).includes(extension.slice(1));
+}
+
+// Value for -f, e.g. markdown+hard_line_breaks-smart; null lets pandoc guess
+function getInputFormat() {
+  const reader = getReader();
+  if (!reader) return null;
+  if (!markdownReaders[reader]) return reader;
+  const extensions = Object.entries(markdownExtensionToggles)
+    .filter(([id, extension]) => $(id).checked && readerSupports(reader, extension))
+    .map(([, extension]) => extension);
+  return reader + extensions.join('');
+}
+
+function updateInputFormatState() {
+  const sources = {
+    extension: 'from the extension',
+    'front-matter': 'from the YAML front matter',
+    content: 'from the content',
+    fallback: 'as a fallback'
+  };
+  const detected = detectedInputFormat && detectedInputFormat.format;
+  $('detectedInputFormat').textContent = detected
+    ? `Detected ${detected} ${sources[detectedInputFormat.source] || ''}`.trim()
+    : (inputFilePath ? 'Not detected, pandoc will guess' : '');
+
+  const reader = getReader();
+  $('markdownExtensions').classList.toggle('opacity-50', !markdownReaders[reader]);
+  Object.entries(markdownExtensionToggles).forEach(([id, extension]) => {
+    const supported = readerSupports(reader, extension);
+    $(id).disabled = !supported;
+    $(id).closest('label').classList.toggle('opacity-50', !supported && !!markdownReaders[reader]);
+  });
+}
+
+// Book projects - the backend keeps the chapter order (its own or from _toc.yml/SUMMARY.md) and
+// watches the chapters; the first chapter stands in as the input for fonts, citations and output
+async function openBookProject() {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const project = await invoke('open_project');
+    if (!project) return;
+    await applyBookProject(project);
+
+    const baseName = project.name.replace(/\s+/g, '-');
+    inputFileName = baseName;
+    outputDirPath = project.root.endsWith('/') ? project.root : project.root + '/';
+    $('outputName').value = baseName;
+    updateOutputDisplay();
+    updateCommandPreview();
+    showToast(`Opened "${project.name}" with ${project.paths.length} chapter${project.paths.length === 1 ? '' : 's'}`, 'success');
+  } catch (e) {
+    showToast(`Failed to open project: ${e}`, 'error');
+  }
+}
+
+async function applyBookProject(project) {
+  bookProject = project;
+  inputFilePath = project.paths[0] || null;
+  $('inputPath').textContent = project.root;
+  $('projectPanel').classList.remove('hidden');
+  renderProjectChapters();
+
+  // Mermaid/diagram detection and the glyph scan look at the whole book
+  try {
+    const { readTextFile } = await import('@tauri-apps/plugin-fs');
+    const texts = await Promise.all(project.paths.map(path => readTextFile(path)));
+    inputFileContent = texts.join('\n\n');
+    detectMermaid(inputFileContent);
+  } catch (e) {
+    console.error('Failed to read chapters:', e);
+    inputFileContent = null;
+  }
+  updateGlyphCoverage();
+  loadProjectFonts();
+  updateCitationCheck();
+  detectInputFormat();
+
+  $('convertBtn').disabled = !inputFilePath;
+  updateCommandPreview();
+}
+
+function renderProjectChapters() {
+  const project = bookProject;
+  $('projectName').textContent = project.name;
+  $('projectOrder').textContent = project.orderFile
+    ? `Order from ${project.orderFile}`
+    : 'Order managed here';
+
+  const chapters = project.chapters;
+  $('projectChapters').replaceChildren(...chapters.map((chapter, index) => {
+    const missing = project.missing.includes(chapter);
+    const unsupported = project.unsupported.includes(chapter);
+    const row = document.createElement('div');
+    row.className = `flex items-center gap-2 bg-base-200 rounded-lg px-2 py-1${missing || unsupported ? ' opacity-60' : ''}`;
+
+    const number = document.createElement('span');
+    number.className = 'text-xs text-base-content/50 w-5 text-right';
+    number.textContent = index + 1;
+    const name = document.createElement('span');
+    name.className = 'flex-1 min-w-0 text-sm font-mono truncate';
+    name.textContent = chapter;
+    name.title = missing
+      ? 'File not found - skipped'
+      : unsupported ? 'Not a Markdown file - skipped' : chapter;
+
+    const move = (offset) => {
+      const order = [...chapters];
+      [order[index], order[index + offset]] = [order[index + offset], order[index]];
+      saveProjectOrder(order);
+    };
+    row.append(
+      number,
+      name,
+      luaFilterButton('↑', 'Earlier in the book', () => move(-1), index === 0),
+      luaFilterButton('↓', 'Later in the book', () => move(1), index === chapters.length - 1),
+      luaFilterButton('✕', 'Leave out of the book', () => saveProjectOrder(chapters.filter(c => c !== chapter)), chapters.length === 1)
+    );
+    return row;
+  }));
+
+  const status = $('projectStatus');
+  const problems = [];
+  if (project.missing.length) problems.push(`Not found, skipped: ${project.missing.join(', ')}`);
+  if (project.unsupported.length) problems.push(`Not Markdown, skipped: ${project.unsupported.join(', ')}`);
+  status.textContent = problems.join(' · ');
+  status.classList.toggle('hidden', problems.length === 0);
+}
+
+// Reordering in the app takes the order over from _toc.yml/SUMMARY.md
+async function saveProjectOrder(chapters) {
+  if (bookProject.orderFile && !confirm(`The chapter order comes from ${bookProject.orderFile}. Manage it here instead?`)) {
+    return;
+  }
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    await applyBookProject(await invoke('save_project', { root: bookProject.root, chapters }));
+    await updateProjectWatch();
+  } catch (e) {
+    showToast(`Failed to save chapter order: ${e}`, 'error');
+  }
+}
+
+async function rescanBookProject() {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    await applyBookProject(await invoke('rescan_project', { root: bookProject.root }));
+    await updateProjectWatch();
+  } catch (e) {
+    showToast(`Failed to rescan project: ${e}`, 'error');
+  }
+}
+
+// Stop watching and hide the chapter list; the input stays as it is
+function leaveBookProject() {
+  bookProject = null;
+  $('watchProject').checked = false;
+  updateProjectWatch();
+  $('projectPanel').classList.add('hidden');
+}
+
+function closeBookProject() {
+  leaveBookProject();
+  inputFilePath = null;
+  inputFileName = null;
+  inputFileContent = null;
+  $('inputPath').textContent = 'No file selected';
+  $('convertBtn').disabled = true;
+  detectedInputFormat = null;
+  updateInputFormatState();
+  updateOutputDisplay();
+  updateCommandPreview();
+}
+
+// The watcher is restarted after order changes so it follows the current chapter list
+async function updateProjectWatch() {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    if (bookProject && $('watchProject').checked) {
+      await invoke('watch_project', { root: bookProject.root });
+    } else {
+      await invoke('unwatch_project');
+    }
+  } catch (e) {
+    showToast(`Failed to watch project: ${e}`, 'error');
+  }
+}
+
+// A saved chapter converts again; a conversion already running picks the change up when it ends
+function convertOnProjectChange() {
+  if ($('convertBtn').disabled) {
+    projectChangePending = true;
+    return;
+  }
+  autoConverting = true;
+  $('convertBtn').click();
+}
+
+async function setupBookProjects() {
+  $('openProjectBtn').disabled = !isTauri;
+  $('openProjectBtn').addEventListener('click', openBookProject);
+  $('rescanProjectBtn').addEventListener('click', rescanBookProject);
+  $('closeProjectBtn').addEventListener('click', closeBookProject);
+  $('watchProject').addEventListener('change', updateProjectWatch);
+  if (!isTauri) return;
+
+  const { listen } = await import('@tauri-apps/api/event');
+  listen('project-changed', async (event) => {
+    const { root, changed, project } = event.payload;
+    if (!bookProject || bookProject.root !== root) return;
+    console.log(`Project changed: ${changed.join(', ')}`);
+    if (project) {
+      await applyBookProject(project);
+    }
+    convertOnProjectChange();
+  });
+}
+
 // Update output display with actual path
 function updateOutputDisplay() {
   const outName = $('outputName').value || 'output';
@@ -311,6 +1316,63 @@ async function handleFileSelect(file) {
 function detectMermaid(content) {
   const hasMermaid = /```mermaid/i.test(content);
   $('mermaidDetected').classList.toggle('hidden', !hasMermaid);
+  detectDiagrams(content);
+}
+
+// Code block classes the backend's diagram filter renders, by renderer
+const diagramLanguages = {
+  plantuml: ['plantuml', 'puml'],
+  graphviz: ['dot', 'graphviz'],
+  d2: ['d2']
+};
+
+// Matches ```dot, ~~~ plantuml and ```{.d2 caption="..."} fences
+function hasDiagramBlock(content, classes) {
+  const pattern = new RegExp(`^\\s*(\`{3,}|~{3,})\\s*\\{?\\s*\\.?(${classes.join('|')})(?![\\w-])`, 'im');
+  return pattern.test(content);
+}
+
+function detectDiagrams(content) {
+  for (const [name, classes] of Object.entries(diagramLanguages)) {
+    $(`${name}Detected`).classList.toggle('hidden', !hasDiagramBlock(content, classes));
+  }
+}
+
+// Renderers the current input needs
+function getDetectedDiagrams() {
+  return Object.keys(diagramLanguages).filter(name => !$(`${name}Detected`).classList.contains('hidden'));
+}
+
+function formatBytes(bytes) {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+// Rendered diagrams are cached by the backend and reused across conversions
+async function updateDiagramCacheSize() {
+  if (!isTauri) return;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const cache = await invoke('get_diagram_cache_size');
+    $('diagramCacheSize').textContent = cache.files
+      ? `${cache.files} cached diagram${cache.files === 1 ? '' : 's'}, ${formatBytes(cache.bytes)}`
+      : 'Empty';
+    $('clearDiagramCacheBtn').disabled = cache.files === 0;
+  } catch (e) {
+    console.log('Could not read diagram cache size:', e);
+  }
+}
+
+async function clearDiagramCache() {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    const freed = await invoke('clear_diagram_cache');
+    showToast(`Cleared ${freed.files} cached diagram${freed.files === 1 ? '' : 's'} (${formatBytes(freed.bytes)})`, 'success');
+  } catch (e) {
+    showToast(`Failed to clear diagram cache: ${e}`, 'error');
+  }
+  updateDiagramCacheSize();
 }
 
 // Format change handling
@@ -324,6 +1386,9 @@ function handleFormatChange() {
 
   // Show/hide standalone option
   $('standaloneLabel').classList.toggle('hidden', !needsStandalone);
+
+  // Template choices for the new format
+  renderTemplates();
 }
 
 // Margin handling
@@ -725,13 +1790,29 @@ function replaceMetadataTokens(str) {
     .replace(/\{user\}/g, 'User');
 }
 
+// Single-quote a value for the command line so $, backticks and backslashes stay literal;
+// an embedded ' is written as '"'"', which the backend's argument splitter reads on every platform
+function shellQuote(value) {
+  return `'${String(value).replace(/'/g, `'"'"'`)}'`;
+}
+
 // Build Pandoc Command
 function buildPandocCommand() {
   const args = ['pandoc'];
 
-  // Input file
-  const input = inputFilePath || 'input.md';
-  args.push(`"${input}"`);
+  // Input file, or every chapter of a book project in order
+  if (bookProject && bookProject.paths.length > 0) {
+    bookProject.paths.forEach(path => args.push(shellQuote(path)));
+  } else {
+    const input = inputFilePath || 'input.md';
+    args.push(shellQuote(input));
+  }
+
+  // Input format; a -f/--from in the extra arguments wins
+  const inputFormat = getInputFormat();
+  if (inputFormat && !/(^|\s)(-f|--from|-r|--read)[\s=]/.test($('extraArgs').value)) {
+    args.push(`-f ${inputFormat}`);
+  }
 
   // Output format
   const format = $('outputFormat').value;
@@ -742,7 +1823,7 @@ function buildPandocCommand() {
   const ext = getExtensionForFormat(format);
   const dir = outputDirPath || './';
   const finalOutput = dir + outName + '.' + ext;
-  args.push(`-o "${finalOutput}"`);
+  args.push(`-o ${shellQuote(finalOutput)}`);
 
   // Standalone flag
   const isPdf = format === 'pdf';
@@ -750,6 +1831,9 @@ function buildPandocCommand() {
     args.push('-s');
   }
 
+  // Variables of the selected template (Eisvogel's title page colors...), added after the app's own
+  const templateVariables = getTemplateVariables();
+
   // PDF-specific options
   if (isPdf) {
     args.push(`--pdf-engine=${$('pdfEngine').value}`);
@@ -785,23 +1869,21 @@ function buildPandocCommand() {
     if ($('titlePage').checked) {
       // Use titlepages package for article class or titlepage for others
       args.push('-V titlepage=true');
-      args.push('-V titlepage-rule-height=0');
+      // A rule height from the template's fields replaces the default of no rule; the field is free
+      // text, so anything but a number falls back to 0
+      const ruleHeight = parseFloat(templateVariables['titlepage-rule-height']);
+      args.push(`-V ${shellQuote(`titlepage-rule-height=${Number.isFinite(ruleHeight) ? ruleHeight : 0}`)}`);
+      delete templateVariables['titlepage-rule-height'];
     }
 
-    // Link colors - dark mode overrides custom colors
-    const isDarkMode = $('darkMode') && $('darkMode').checked;
-    if (isDarkMode) {
-      // Set link colors for dark mode (cyan works well on dark background)
-      args.push('-V colorlinks=true');
-      args.push('-V linkcolor=cyan');
-      args.push('-V urlcolor=cyan');
-      args.push('-V filecolor=cyan');
-      args.push('-V citecolor=cyan');
-      // Mark that dark mode is enabled (placeholder for header path)
-      // This will be replaced during conversion with actual -H flag
-      args.push('--DARK-MODE-PLACEHOLDER--');
+    // Link colors - the color theme overrides custom colors
+    const isThemed = $('darkMode') && $('darkMode').checked;
+    if (isThemed) {
+      // The theme header sets page, text, heading, code and link colors
+      // Placeholder is replaced during conversion with the generated -H header
+      args.push(`--THEME-HEADER-PLACEHOLDER=${getSelectedOutputTheme().id}--`);
     } else if ($('colorLinks').checked) {
-      // Custom link colors (only if not in dark mode)
+      // Custom link colors (only if no color theme)
       args.push('-V colorlinks=true');
       const color = $('linkColor').value.replace('#', '');
       args.push(`-V 'linkcolor=[HTML]{${color}}'`);
@@ -862,29 +1944,30 @@ function buildPandocCommand() {
     }
 
     // Add custom headers/footers if specified
-    if (headerLeft) args.push(`-V header-includes="\\fancyhead[L]{${headerLeft}}"`);
-    if (headerCenter) args.push(`-V header-includes="\\fancyhead[C]{${headerCenter}}"`);
-    if (headerRight) args.push(`-V header-includes="\\fancyhead[R]{${headerRight}}"`);
-    if (footerLeft) args.push(`-V header-includes="\\fancyfoot[L]{${footerLeft}}"`);
-    if (footerCenter) args.push(`-V header-includes="\\fancyfoot[C]{${footerCenter}}"`);
-    if (footerRight) args.push(`-V header-includes="\\fancyfoot[R]{${footerRight}}"`);
+    if (headerLeft) args.push(`-V ${shellQuote(`header-includes=\\fancyhead[L]{${headerLeft}}`)}`);
+    if (headerCenter) args.push(`-V ${shellQuote(`header-includes=\\fancyhead[C]{${headerCenter}}`)}`);
+    if (headerRight) args.push(`-V ${shellQuote(`header-includes=\\fancyhead[R]{${headerRight}}`)}`);
+    if (footerLeft) args.push(`-V ${shellQuote(`header-includes=\\fancyfoot[L]{${footerLeft}}`)}`);
+    if (footerCenter) args.push(`-V ${shellQuote(`header-includes=\\fancyfoot[C]{${footerCenter}}`)}`);
+    if (footerRight) args.push(`-V ${shellQuote(`header-includes=\\fancyfoot[R]{${footerRight}}`)}`);
 
     // Add page number if no custom footer at that position
     if (!hasCustomHeadersFooters) {
       if (pageNumHeader) {
-        args.push(`-V header-includes="\\fancyhead[${pageNumPosition}]{${pageNumStr}}"`);
+        args.push(`-V ${shellQuote(`header-includes=\\fancyhead[${pageNumPosition}]{${pageNumStr}}`)}`);
       } else {
-        args.push(`-V header-includes="\\fancyfoot[${pageNumPosition}]{${pageNumStr}}"`);
+        args.push(`-V ${shellQuote(`header-includes=\\fancyfoot[${pageNumPosition}]{${pageNumStr}}`)}`);
       }
     }
   }
 
   // Typography
-  if ($('mainFont').value) {
-    args.push(`-V mainfont="${$('mainFont').value}"`);
+  // Project fonts are loaded by file name; the backend adds their mainfont/monofont variables
+  if ($('mainFont').value && !isProjectFont($('mainFont').value)) {
+    args.push(`-V ${shellQuote(`mainfont=${$('mainFont').value}`)}`);
   }
-  if ($('monoFont').value) {
-    args.push(`-V monofont="${$('monoFont').value}"`);
+  if ($('monoFont').value && !isProjectFont($('monoFont').value)) {
+    args.push(`-V ${shellQuote(`monofont=${$('monoFont').value}`)}`);
   }
   const fontSize = $('fontSize').value;
   if (fontSize && fontSize !== '12') {
@@ -930,24 +2013,34 @@ function buildPandocCommand() {
   const author = replaceMetadataTokens(getTokenFieldValue($('docAuthor')));
   const date = replaceMetadataTokens(getTokenFieldValue($('docDate')));
 
-  if (title) args.push(`-M title="${title}"`);
-  if (author) args.push(`-M author="${author}"`);
-  if (date) args.push(`-M date="${date}"`);
+  if (title) args.push(`-M ${shellQuote(`title=${title}`)}`);
+  if (author) args.push(`-M ${shellQuote(`author=${author}`)}`);
+  if (date) args.push(`-M ${shellQuote(`date=${date}`)}`);
 
   // Mermaid filter - configured via environment variables in Rust backend
   const hasMermaid = !$('mermaidDetected').classList.contains('hidden');
   if (hasMermaid) {
     args.push('-F mermaid-filter');
-    // Note: mermaid-filter is configured via MERMAID_FILTER_* environment variables
-    // in the Rust backend to use SVG format with transparent background
+    // The backend swaps this for its diagram filter, which reuses cached renders and only runs
+    // mermaid-filter (with the preset's theme, format, size and CSS) for new diagrams
   }
 
-  // Dark mode for HTML/EPUB
+  // Rendered diagrams live in a per-job temp workspace or the app cache, so HTML must embed them
+  if ((hasMermaid || getDetectedDiagrams().length > 0) && format === 'html') {
+    args.push('--embed-resources');
+  }
+
+  // Color theme for HTML/EPUB (same palette as the PDF header)
   const isHtmlLike = ['html', 'epub'].includes(format);
   if (isHtmlLike && $('darkMode') && $('darkMode').checked) {
-    args.push('--css=data:text/css,body{background-color:%231e1e2e;color:%23cdd6f4}a{color:%2389b4fa}pre,code{background-color:%23313244}');
+    const css = getSelectedOutputTheme().css.replace(/#/g, '%23');
+    args.push(`--css=${shellQuote(`data:text/css,${css}`)}`);
   }
 
+  Object.entries(templateVariables).forEach(([name, value]) => {
+    args.push(`-V ${shellQuote(`${name}=${value === true ? 'true' : value}`)}`);
+  });
+
   // Other filters
   if ($('filterCrossref').checked) args.push('-F pandoc-crossref');
   if ($('filterCiteproc').checked) args.push('--citeproc');
@@ -1022,6 +2115,8 @@ function showToast(message, type = 'info') {
 // Conversion
 function setupConversion() {
   $('convertBtn').addEventListener('click', async () => {
+    const automatic = autoConverting;
+    autoConverting = false;
     if (!inputFilePath) {
       showToast('Please select an input file first', 'warning');
       return;
@@ -1036,7 +2131,7 @@ function setupConversion() {
       try {
         const { invoke } = await import('@tauri-apps/api/core');
         const exists = await invoke('file_exists', { path: finalPath });
-        if (exists) {
+        if (exists && !automatic) {
           if (!confirm(`File "${outName}.${ext}" already exists. Overwrite?`)) {
             return;
           }
@@ -1046,6 +2141,28 @@ function setupConversion() {
       }
     }
 
+    // Missing bold/italic faces turn into fontspec warnings and silently upright text
+    if ($('outputFormat').value === 'pdf') {
+      const problems = getMissingFontStyles();
+      if (problems.length > 0 && !confirm(`${problems.join('\n')}\n\nBold or italic text will be faked or rendered upright. Convert anyway?`)) {
+        return;
+      }
+    }
+
+    // pandoc renders undefined keys as "(key?)" and duplicates only pick one entry silently
+    const citationReport = await updateCitationCheck();
+    if (citationReport && citationReport.undefined.length > 0 && !automatic) {
+      if (!confirm(`Citation keys not found in the bibliography:\n${citationReport.undefined.join(', ')}\n\nThey will appear as (key?) in the output. Convert anyway?`)) {
+        return;
+      }
+    }
+    if (citationReport && citationReport.duplicates.length > 0) {
+      const lines = citationReport.duplicates.map(d => `${d.key} (${d.files.length} entries)`);
+      if (!confirm(`Duplicate bibliography keys:\n${lines.join('\n')}\n\nOnly one entry per key will be used. Convert anyway?`)) {
+        return;
+      }
+    }
+
     $('statusArea').classList.remove('hidden');
     $('statusText').textContent = 'Converting...';
     $('progressBar').max = 100;
@@ -1057,28 +2174,44 @@ function setupConversion() {
         const { invoke } = await import('@tauri-apps/api/core');
         let command = buildPandocCommand().replace(/\\\n\s+/g, ' ');
 
-        // Handle dark mode header file for PDF
-        if (command.includes('--DARK-MODE-PLACEHOLDER--')) {
-          $('progressBar').value = 25;
-          $('statusText').textContent = 'Preparing dark mode...';
-          console.log('Dark mode detected, writing header file...');
-          const headerPath = await invoke('write_dark_mode_header');
-          console.log('Dark mode header written to:', headerPath);
-          const beforeReplace = command;
-          command = command.replace('--DARK-MODE-PLACEHOLDER--', `-H "${headerPath}"`);
-          if (beforeReplace === command) {
-            console.warn('WARNING: Placeholder replacement had no effect');
-          }
+        // Color theme and Unicode fallback headers for PDF are generated by the backend in the job workspace
+        const options = {};
+        if ($('outputFormat').value === 'pdf' && $('unicodeFallback').checked) {
+          options.unicodeHeader = getUnicodeHeaderOptions();
+        }
+        // Lets the backend allow opening the result afterwards
+        options.outputPath = finalPath;
+        options.inputPath = inputFilePath;
+        options.chapters = bookProject ? bookProject.paths : [];
+        options.workingDir = getWorkingDir();
+        options.env = getJobEnvironment();
+        options.assetDirs = $('assetDirs').value.split(';').map(d => d.trim()).filter(Boolean);
+        options.outputFormat = $('outputFormat').value;
+        options.mermaid = getMermaidOptions();
+        options.diagrams = getDetectedDiagrams();
+        options.luaFilters = getEnabledLuaFilters();
+        options.citations = getCitationOptions();
+        const template = getSelectedTemplate();
+        options.templateId = template ? template.id : null;
+        const projectMain = isProjectFont($('mainFont').value) ? $('mainFont').value : null;
+        const projectMono = isProjectFont($('monoFont').value) ? $('monoFont').value : null;
+        if ($('outputFormat').value === 'pdf' && projectFonts.dir && (projectMain || projectMono)) {
+          options.projectFonts = { dir: projectFonts.dir, mainFont: projectMain, monoFont: projectMono };
+        }
+        const themeMatch = command.match(/--THEME-HEADER-PLACEHOLDER=([a-z0-9-]+)--/);
+        if (themeMatch) {
+          options.themeId = themeMatch[1];
+          command = command.replace(themeMatch[0], '');
         }
 
         // Safety check: ensure no placeholder made it through
-        if (command.includes('--DARK-MODE-PLACEHOLDER--')) {
-          throw new Error('Dark mode header placeholder was not properly replaced. Please try disabling dark mode or contact support.');
+        if (command.includes('--THEME-HEADER-PLACEHOLDER')) {
+          throw new Error('Color theme header placeholder was not properly replaced. Please try disabling the color theme or contact support.');
         }
 
         $('progressBar').value = 50;
         $('statusText').textContent = 'Running pandoc...';
-        await invoke('run_pandoc', { command });
+        await invoke('run_pandoc', { command, options });
 
         $('progressBar').value = 90;
         $('statusText').textContent = 'Finalizing...';
@@ -1087,10 +2220,15 @@ function setupConversion() {
           $('progressBar').value = 100;
         }, 200);
         showToast('Document converted successfully!', 'success');
+        updateDiagramCacheSize();
 
         // Open file if checkbox is checked
         if ($('openOnComplete').checked) {
-          await invoke('open_file', { path: finalPath });
+          try {
+            await invoke('open_file', { path: finalPath });
+          } catch (e) {
+            showToast(`Could not open output: ${e}`, 'warning');
+          }
         }
       } else {
         // Web mode - can't actually run pandoc
@@ -1105,6 +2243,10 @@ function setupConversion() {
       showToast('Conversion failed: ' + err, 'error');
     } finally {
       $('convertBtn').disabled = !inputFilePath;
+      if (projectChangePending) {
+        projectChangePending = false;
+        convertOnProjectChange();
+      }
     }
   });
 }
@@ -1141,15 +2283,18 @@ function savePresetsToStorage(presets) {
 function getSettingsIds() {
   return [
     'outputFormat', 'pdfEngine', 'titlePage', 'toc', 'lof', 'lot',
-    'numberSections', 'standalone', 'darkMode', 'tocDepth', 'tocNewPage', 'topLevelDiv',
+    'numberSections', 'standalone', 'darkMode', 'outputTheme', 'tocDepth', 'tocNewPage', 'topLevelDiv',
     'paperSize', 'orientation', 'marginUnit', 'uniformMargins', 'marginAll',
     'marginTop', 'marginBottom', 'marginLeft', 'marginRight',
     'headerLeft', 'headerCenter', 'headerRight', 'footerLeft', 'footerCenter', 'footerRight',
     'pageNumberFormat', 'pageNumberStyle', 'pageNumberPosition',
-    'mainFont', 'monoFont', 'fontSize', 'lineHeight',
+    'mainFont', 'monoFont', 'projectFontsDir', 'unicodeFallback', 'fontSize', 'lineHeight',
     'highlightTheme', 'lineNumbers', 'codeBlockBg', 'codeBlockBgColor',
     'docTitle', 'docAuthor', 'docDate', 'documentClass',
-    'filterCrossref', 'filterCiteproc', 'extraArgs', 'colorLinks', 'linkColor', 'openOnComplete'
+    'fromFormat', 'mdHardLineBreaks', 'mdNoSmart', 'mdEmoji',
+    'filterCrossref', 'filterCiteproc', 'extraArgs', 'mermaidTheme', 'mermaidFormat', 'mermaidWidth', 'mermaidScale', 'mermaidFont',
+    'mermaidBackground', 'mermaidCss', 'luaFilters', 'templateIds', 'templateVars', 'bibliographies', 'cslStyle', 'cslCustom', 'citationLocale', 'workingDir', 'workingDirCustom', 'assetDirs', 'envVars',
+    'colorLinks', 'linkColor', 'openOnComplete'
   ];
 }
 
@@ -1195,9 +2340,15 @@ function applySettings(settings) {
   // Trigger UI updates
   $('uniformMargins').dispatchEvent(new Event('change'));
   $('toc').dispatchEvent(new Event('change'));
+  $('workingDir').dispatchEvent(new Event('change'));
+  $('cslStyle').dispatchEvent(new Event('change'));
+  updateInputFormatState();
+  renderLuaFilters();
+  updateCitationCheck();
   handleFormatChange();
   updateCodePreview();
   updateCommandPreview();
+  updateFontStyleWarning();
 }
 
 function updatePresetDropdown() {
@@ -1306,6 +2457,25 @@ function setupFabMenu() {
     await checkDependencies();
   });
 
+  // Environment report for bug reports
+  $('fabDoctor')?.addEventListener('click', async (e) => {
+    e.preventDefault();
+    document.activeElement?.blur();
+    if (!isTauri) {
+      showToast('Doctor report is only available in the desktop app', 'info');
+      return;
+    }
+    showToast('Collecting environment report...', 'info');
+    try {
+      const { invoke } = await import('@tauri-apps/api/core');
+      const report = await invoke('run_doctor');
+      await navigator.clipboard.writeText(await invoke('doctor_report_markdown', { report }));
+      showToast('Doctor report copied to clipboard!', 'success');
+    } catch (err) {
+      showToast('Failed to build doctor report: ' + err, 'error');
+    }
+  });
+
   // Reset to defaults
   $('fabResetDefaults')?.addEventListener('click', (e) => {
     e.preventDefault();
@@ -1360,6 +2530,20 @@ const depInstallInfo = {
   'pandoc-crossref': {
     brew: 'brew install pandoc-crossref',
     url: 'https://github.com/lierdakil/pandoc-crossref'
+  },
+  'plantuml': {
+    brew: 'brew install plantuml',
+    apt: 'sudo apt install plantuml',
+    url: 'https://plantuml.com/'
+  },
+  'graphviz': {
+    brew: 'brew install graphviz',
+    apt: 'sudo apt install graphviz',
+    url: 'https://graphviz.org/'
+  },
+  'd2': {
+    brew: 'brew install d2',
+    url: 'https://d2lang.com/'
   }
 };
 
@@ -1377,11 +2561,14 @@ async function checkDependencies() {
   modal.showModal();
 
   const deps = [
-    { name: 'pandoc', cmd: 'pandoc --version', required: true, desc: 'Document converter (required)' },
-    { name: 'tectonic', cmd: 'tectonic --version', required: false, desc: 'PDF engine - auto-downloads packages (recommended)' },
-    { name: 'texlive', cmd: 'pdflatex --version', required: false, desc: 'TeX Live - includes pdflatex, xelatex, lualatex' },
-    { name: 'mermaid-filter', cmd: 'which mermaid-filter', required: false, desc: 'Mermaid diagram support' },
-    { name: 'pandoc-crossref', cmd: 'pandoc-crossref --version', required: false, desc: 'Cross-reference filter' },
+    { name: 'pandoc', required: true, desc: 'Document converter (required)' },
+    { name: 'tectonic', required: false, desc: 'PDF engine - auto-downloads packages (recommended)' },
+    { name: 'texlive', required: false, desc: 'TeX Live - includes pdflatex, xelatex, lualatex' },
+    { name: 'mermaid-filter', required: false, desc: 'Mermaid diagram support' },
+    { name: 'pandoc-crossref', required: false, desc: 'Cross-reference filter' },
+    { name: 'plantuml', required: false, desc: 'PlantUML diagram support' },
+    { name: 'graphviz', required: false, desc: 'Graphviz (dot) diagram support' },
+    { name: 'd2', required: false, desc: 'D2 diagram support' },
   ];
 
   const checkResults = [];
@@ -1391,7 +2578,7 @@ async function checkDependencies() {
 
     for (const dep of deps) {
       try {
-        const result = await invoke('check_command', { command: dep.cmd });
+        const result = await invoke('detect_dependency', { name: dep.name });
         const version = extractVersion(result, dep.name);
         checkResults.push({ ...dep, installed: true, version });
         installedDeps[dep.name] = true;
@@ -1408,6 +2595,7 @@ async function checkDependencies() {
 
   // Update UI based on installed deps
   updateFeatureAvailability();
+  loadCommunityTemplates();
 
   // Render results
   results.innerHTML = checkResults.map(dep => {
@@ -1480,6 +2668,19 @@ async function checkDependencies() {
       const depName = btn.dataset.dep;
 
       if (isTauri) {
+        // Preview the recipe first; only ask when it will prompt for an admin password
+        const plan = await previewDepOperation('install_dependency', { name: depName, method });
+        if (plan && plan.steps.some(step => step.privilege !== 'none')) {
+          const { ask } = await import('@tauri-apps/plugin-dialog');
+          const confirmed = await ask(`Install ${depName}?\n\n${describeDepPlan(plan)}`, {
+            title: 'Confirm Install',
+            kind: 'warning'
+          });
+          if (!confirmed) {
+            return;
+          }
+        }
+
         await runDepOperation('install_dependency', { name: depName, method }, `Installing ${depName}`);
       } else {
         // Web mode - show info
@@ -1499,7 +2700,8 @@ async function checkDependencies() {
       if (isTauri) {
         // Use Tauri's dialog API for proper async confirmation
         const { ask } = await import('@tauri-apps/plugin-dialog');
-        const confirmed = await ask(`Reinstall ${depName}? This will uninstall and reinstall the package.`, {
+        const plan = await previewDepOperation('reinstall_dependency', { name: depName, method });
+        const confirmed = await ask(`Reinstall ${depName}? This will uninstall and reinstall the package.${plan ? '\n\n' + describeDepPlan(plan) : ''}`, {
           title: 'Confirm Reinstall',
           kind: 'warning'
         });
@@ -1523,7 +2725,8 @@ async function checkDependencies() {
       if (isTauri) {
         // Use Tauri's dialog API for proper async confirmation
         const { ask } = await import('@tauri-apps/plugin-dialog');
-        const confirmed = await ask(`Are you sure you want to uninstall ${depName}?`, {
+        const plan = await previewDepOperation('uninstall_dependency', { name: depName });
+        const confirmed = await ask(`Are you sure you want to uninstall ${depName}?${plan ? '\n\n' + describeDepPlan(plan) : ''}`, {
           title: 'Confirm Uninstall',
           kind: 'warning'
         });
@@ -1538,6 +2741,148 @@ async function checkDependencies() {
   });
 }
 
+// Community templates (Eisvogel...) installed into pandoc's templates folder, with their versions
+async function loadCommunityTemplates() {
+  const container = $('communityTemplates');
+  if (!isTauri) {
+    container.textContent = 'Community templates can only be installed in the desktop app';
+    ['communityTemplateUrl', 'installTemplateUrlBtn', 'installTemplateArchiveBtn'].forEach(id => { $(id).disabled = true; });
+    return;
+  }
+
+  let catalog;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    catalog = await invoke('list_community_templates');
+  } catch (e) {
+    container.textContent = `Could not list community templates: ${e}`;
+    return;
+  }
+  $('communityTemplatesDir').textContent = `Installed into ${catalog.dir}`;
+
+  container.replaceChildren(...catalog.templates.map(template => {
+    const row = document.createElement('div');
+    row.className = 'flex items-start gap-3 p-2 rounded-lg bg-base-200';
+
+    const text = document.createElement('div');
+    text.className = 'flex-1 min-w-0';
+    const name = document.createElement('div');
+    name.className = 'font-medium';
+    name.textContent = template.name;
+    const desc = document.createElement('div');
+    desc.className = 'text-xs text-base-content/70 truncate';
+    desc.textContent = template.description;
+    desc.title = template.description;
+    const version = document.createElement('div');
+    version.className = 'text-xs font-mono text-base-content/50';
+    const installed = template.installed;
+    const outdated = installed && template.latestVersion && installed.version !== template.latestVersion;
+    version.textContent = installed
+      ? `${installed.version} installed${outdated ? ` · ${template.latestVersion} available` : ''} (${installed.files.join(', ')})`
+      : `Not installed${template.latestVersion ? ` · ${template.latestVersion}` : ''}`;
+    text.append(name, desc, version);
+
+    const buttons = document.createElement('div');
+    buttons.className = 'flex gap-1 mt-1 flex-wrap';
+    const button = (label, className, onClick) => {
+      const btn = document.createElement('button');
+      btn.type = 'button';
+      btn.className = `btn btn-xs ${className}`;
+      btn.textContent = label;
+      btn.addEventListener('click', onClick);
+      buttons.append(btn);
+    };
+    if (template.latestVersion && (!installed || outdated)) {
+      button(installed ? 'Update' : 'Install', 'btn-primary', () => installCommunityTemplate({ kind: 'catalog', value: template.id }, template.name));
+    }
+    if (installed) {
+      button('Uninstall', 'btn-ghost text-error', () => uninstallCommunityTemplate(template));
+    }
+    if (template.homepage) {
+      const link = document.createElement('a');
+      link.href = template.homepage;
+      link.target = '_blank';
+      link.className = 'btn btn-xs btn-ghost';
+      link.textContent = 'Docs';
+      buttons.append(link);
+    }
+    text.append(buttons);
+    row.append(text);
+    return row;
+  }));
+
+  // The template picker lists installed community templates too
+  await loadTemplates();
+}
+
+async function installCommunityTemplate(source, label) {
+  await runDepOperation('install_community_template', { source }, `Installing ${label}`);
+}
+
+async function uninstallCommunityTemplate(template) {
+  const { ask } = await import('@tauri-apps/plugin-dialog');
+  const confirmed = await ask(`Remove ${template.name} (${template.installed.files.join(', ')}) from pandoc's templates folder?`, {
+    title: 'Confirm Uninstall',
+    kind: 'warning'
+  });
+  if (!confirmed) return;
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    await invoke('uninstall_community_template', { id: template.id });
+    showToast(`${template.name} uninstalled`, 'success');
+  } catch (e) {
+    showToast(`Failed to uninstall ${template.name}: ${e}`, 'error');
+  }
+  await loadCommunityTemplates();
+}
+
+function setupCommunityTemplates() {
+  $('installTemplateUrlBtn').addEventListener('click', () => {
+    const url = $('communityTemplateUrl').value.trim();
+    if (!url.startsWith('https://')) {
+      showToast('Enter an https:// link to a template archive or file', 'error');
+      return;
+    }
+    installCommunityTemplate({ kind: 'url', value: url }, url.split('/').pop() || 'template');
+  });
+  $('installTemplateArchiveBtn').addEventListener('click', () => {
+    installCommunityTemplate({ kind: 'archive' }, 'template from archive');
+  });
+}
+
+// Ask the backend what a dependency operation would do without running it
+async function previewDepOperation(command, params) {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke(command, { ...params, dryRun: true });
+  } catch (e) {
+    console.error('Failed to preview dependency operation:', e);
+    return null;
+  }
+}
+
+// Summarize a dry-run plan for a confirmation dialog
+function describeDepPlan(plan) {
+  const privilegeText = {
+    'none': 'no admin password needed',
+    'required': 'requires an admin password',
+    'fallback': 'may ask for an admin password'
+  };
+  return plan.steps.map(step => {
+    const lines = [`${step.operation} via ${step.manager} (${privilegeText[step.privilege] || step.privilege}${step.elevation ? ', ' + step.elevation : ''})`];
+    lines.push(`Packages: ${step.packages.join(', ')}`);
+    if (!step.nativeDryRun) {
+      // Nothing was checked, so an empty change list would be misleading
+      lines.push(`${step.manager} has no dry-run for this step; it would run: ${step.command}`);
+    } else if (step.changes.length > 0) {
+      lines.push(`Would change: ${step.changes.slice(0, 10).join(', ')}${step.changes.length > 10 ? ` (+${step.changes.length - 10} more)` : ''}`);
+    } else {
+      lines.push('Would change: nothing reported');
+    }
+    return lines.join('\n');
+  }).join('\n\n') + (plan.commandsOnly ? '\n\nSome steps are listed as commands only; their changes could not be checked in advance.' : '');
+}
+
 // Run a dependency operation with progress modal
 async function runDepOperation(command, params, title) {
   const progressModal = $('progressModal');
@@ -1583,9 +2928,11 @@ async function runDepOperation(command, params, title) {
       } else {
         progressOutput.textContent += `\n✗ ${data.message}\n`;
       }
-      // Show close button, hide cancel
-      cancelBtn.classList.add('hidden');
-      closeBtn.classList.remove('hidden');
+    } else if (data.type === 'phase') {
+      // Reinstall reports check/uninstall/install/verify/rollback phases separately
+      const marker = { start: '▶', success: '✓', failed: '✗', skipped: '–' }[data.status] || '•';
+      progressOutput.textContent += `\n${marker} [${data.phase}] ${data.message}\n`;
+      progressOutput.scrollTop = progressOutput.scrollHeight;
     }
   });
 
@@ -1614,14 +2961,15 @@ async function runDepOperation(command, params, title) {
     checkDependencies();
   };
 
-  // Run the command
+  // Run the command (may span several streamed sub-commands, e.g. reinstall)
   try {
     await invoke(command, params);
   } catch (e) {
     progressOutput.textContent += `\n✗ Error: ${e}\n`;
-    cancelBtn.classList.add('hidden');
-    closeBtn.classList.remove('hidden');
   }
+  // Show close button, hide cancel
+  cancelBtn.classList.add('hidden');
+  closeBtn.classList.remove('hidden');
 }
 
 // Extract version from command output
@@ -1725,6 +3073,19 @@ function updateFeatureAvailability() {
     }
   }
 
+  // Diagram renderer badges
+  for (const name of Object.keys(diagramLanguages)) {
+    const badge = $(`${name}Detected`);
+    if (!badge) continue;
+    badge.classList.toggle('badge-success', !!installedDeps[name]);
+    badge.classList.toggle('badge-warning', !installedDeps[name]);
+    if (installedDeps[name]) {
+      badge.removeAttribute('title');
+    } else {
+      badge.setAttribute('title', `${name} not installed. Check Dependencies to install.`);
+    }
+  }
+
   // Crossref checkbox
   const crossrefCheckbox = $('filterCrossref');
   const crossrefLabel = crossrefCheckbox?.closest('label');
@@ -1750,23 +3111,18 @@ async function checkDependenciesSilent() {
     return;
   }
 
-  const deps = [
-    { name: 'pandoc', cmd: 'pandoc --version' },
-    { name: 'tectonic', cmd: 'tectonic --version' },
-    { name: 'texlive', cmd: 'pdflatex --version' },  // Check for TeX Live via pdflatex
-    { name: 'mermaid-filter', cmd: 'which mermaid-filter' },
-    { name: 'pandoc-crossref', cmd: 'pandoc-crossref --version' },
-  ];
+  // The backend knows how to detect each one (TeX Live via pdflatex, mermaid-filter via PATH)
+  const deps = ['pandoc', 'tectonic', 'texlive', 'mermaid-filter', 'pandoc-crossref', 'plantuml', 'graphviz', 'd2'];
 
   try {
     const { invoke } = await import('@tauri-apps/api/core');
 
-    for (const dep of deps) {
+    for (const name of deps) {
       try {
-        await invoke('check_command', { command: dep.cmd });
-        installedDeps[dep.name] = true;
+        await invoke('detect_dependency', { name });
+        installedDeps[name] = true;
       } catch {
-        installedDeps[dep.name] = false;
+        installedDeps[name] = false;
       }
     }
   } catch (e) {
@@ -1795,6 +3151,8 @@ function resetToDefaults() {
   // Fonts
   $('mainFont').value = '';
   $('monoFont').value = '';
+  $('projectFontsDir').value = '';
+  $('unicodeFallback').checked = false;
   $('highlightTheme').value = 'breezedark';
   $('lineNumbers').checked = false;
   $('codeBlockBg').checked = true;
@@ -1813,6 +3171,7 @@ function resetToDefaults() {
   $('documentClassLabel').textContent = 'Report';
   $('topLevelDiv').value = 'default';
   if ($('darkMode')) $('darkMode').checked = false;
+  if ($('outputTheme')) $('outputTheme').value = 'dark';
 
   // Content (use innerHTML for contenteditable fields)
   $('docTitle').innerHTML = '';
@@ -1829,16 +3188,43 @@ function resetToDefaults() {
   $('pageNumberStyle').value = 'arabic';
 
   // Advanced
+  $('mermaidTheme').value = 'default';
+  $('mermaidFormat').value = 'auto';
+  $('mermaidWidth').value = '';
+  $('mermaidScale').value = '';
+  $('mermaidFont').value = '';
+  $('mermaidBackground').value = '';
+  $('mermaidCss').value = '';
+  $('fromFormat').value = 'auto';
+  $('mdHardLineBreaks').checked = false;
+  $('mdNoSmart').checked = false;
+  $('mdEmoji').checked = false;
   $('filterCrossref').checked = false;
   $('filterCiteproc').checked = false;
+  $('luaFilters').value = '';
+  $('templateIds').value = '';
+  $('templateVars').value = '';
+  $('bibliographies').value = '';
+  $('cslStyle').value = 'chicago';
+  $('cslCustom').value = '';
+  $('citationLocale').value = '';
   $('colorLinks').checked = true;
   $('linkColor').value = '#0066cc';
   $('extraArgs').value = '';
+  $('workingDir').value = 'input';
+  $('workingDirCustom').value = '';
+  $('assetDirs').value = '';
+  $('envVars').value = '';
   $('openOnComplete').checked = true;
 
   // Trigger UI updates
   $('uniformMargins').dispatchEvent(new Event('change'));
   $('toc').dispatchEvent(new Event('change'));
+  $('workingDir').dispatchEvent(new Event('change'));
+  $('cslStyle').dispatchEvent(new Event('change'));
+  updateInputFormatState();
+  renderLuaFilters();
+  updateCitationCheck();
   handleFormatChange();
   updateCodePreview();
   updateCommandPreview();
@@ -1864,6 +3250,7 @@ function setupPdfEngineDropdown() {
       label.textContent = text;
       hiddenInput.value = value;
       updateCommandPreview();
+      updateUnicodeCoverage();
 
       // Close dropdown
       document.activeElement?.blur();
@@ -2240,6 +3627,31 @@ async function init() {
 
   initTheme();
   await loadSystemFonts();
+  await loadOutputThemes();
+  await loadLuaFilters();
+  await loadTemplates();
+  ['unicodeFallback', 'mainFont', 'monoFont'].forEach(id => $(id).addEventListener('change', updateUnicodeCoverage));
+  ['mainFont', 'monoFont'].forEach(id => $(id).addEventListener('change', updateGlyphCoverage));
+  ['mainFont', 'monoFont'].forEach(id => $(id).addEventListener('change', updateFontStyleWarning));
+  $('projectFontsDir').addEventListener('change', loadProjectFonts);
+  $('clearDiagramCacheBtn').addEventListener('click', clearDiagramCache);
+  $('addLuaFilterBtn').addEventListener('click', addLuaFilter);
+  $('templateSelect').addEventListener('change', () => {
+    setPresetTemplate(templateKinds[$('outputFormat').value], $('templateSelect').value);
+  });
+  $('importTemplateBtn').addEventListener('click', importTemplate);
+  $('exportDefaultTemplateBtn').addEventListener('click', exportDefaultTemplate);
+  $('openTemplateBtn').addEventListener('click', openTemplate);
+  $('deleteTemplateBtn').addEventListener('click', deleteTemplate);
+  window.addEventListener('focus', () => {
+    templateVariableCache.clear();
+    renderTemplates();
+  });
+  $('workingDir').addEventListener('change', () => {
+    $('workingDirCustom').classList.toggle('hidden', $('workingDir').value !== 'custom');
+  });
+  $('fromFormat').addEventListener('change', updateInputFormatState);
+  updateInputFormatState();
   setupFileHandling();
   setupMargins();
   setupCodePreview();
@@ -2249,6 +3661,9 @@ async function init() {
   setupConversion();
   setupInputListeners();
   setupPresets();
+  setupCitations();
+  setupCommunityTemplates();
+  setupBookProjects();
   setupFabMenu();
   setupPdfEngineDropdown();
   setupFabMenu2();
@@ -2264,6 +3679,7 @@ async function init() {
 
   // Check dependencies silently to enable/disable features
   checkDependenciesSilent();
+  updateDiagramCacheSize();
 
   // Check for updates silently on startup (don't block or show error)
   if (isTauri) {